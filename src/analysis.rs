@@ -0,0 +1,344 @@
+//! Monte-Carlo analysis of the generator: many boards are generated and what ends up on them
+//! is counted.
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    board::{pick, FieldSize, BOARD_CELLS},
+    card::CardRow,
+};
+
+pub(crate) struct CardFrequency {
+    pub(crate) category: String,
+    pub(crate) text: String,
+    pub(crate) weight: f64,
+    pub(crate) count: usize,
+}
+
+/// Results of running the generator many times with the same options.
+pub(crate) struct Analysis {
+    pub(crate) runs: usize,
+    pub(crate) failures: usize,
+    /// Number of cards placed on each successful board.
+    pub(crate) cells: usize,
+    pub(crate) cards: Vec<CardFrequency>,
+    pub(crate) categories: BTreeMap<String, usize>,
+    pub(crate) line_mean: f64,
+    pub(crate) line_variance: f64,
+    /// Average difference between the heaviest and lightest line of a board.
+    pub(crate) line_spread: f64,
+}
+
+#[derive(serde::Serialize)]
+struct AnalysisRecord<'a> {
+    section: &'a str,
+    category: &'a str,
+    text: &'a str,
+    weight: Option<f64>,
+    count: usize,
+    frequency: f64,
+}
+
+/// Generator options the analysis runs with.
+pub(crate) struct AnalysisOptions {
+    pub(crate) size: FieldSize,
+    pub(crate) free_cell: Option<usize>,
+    pub(crate) weighted: bool,
+    pub(crate) quotas: BTreeMap<String, usize>,
+    pub(crate) runs: usize,
+}
+
+/// Analysis running on its own thread, it is stopped when dropped.
+pub(crate) struct AnalysisTask {
+    receiver: Receiver<Analysis>,
+    stop: Arc<AtomicBool>,
+    done: Arc<AtomicUsize>,
+    runs: usize,
+}
+
+impl AnalysisTask {
+    /// `notify` is called when the result is ready.
+    pub(crate) fn start(
+        pool: Vec<CardRow>,
+        options: AnalysisOptions,
+        notify: impl Fn() + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let done = Arc::new(AtomicUsize::new(0));
+        let (thread_stop, thread_done) = (stop.clone(), done.clone());
+        let runs = options.runs;
+
+        thread::spawn(move || {
+            let pool = pool.iter().collect::<Vec<_>>();
+            let analysis = Analysis::run(&pool, &options, &mut thread_rng(), |finished| {
+                thread_done.store(finished, Ordering::Relaxed);
+                !thread_stop.load(Ordering::Relaxed)
+            });
+
+            if let Some(analysis) = analysis {
+                let _ = sender.send(analysis);
+                notify();
+            }
+        });
+
+        Self {
+            receiver,
+            stop,
+            done,
+            runs,
+        }
+    }
+
+    pub(crate) fn try_recv(&self) -> Result<Analysis, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    /// Share of the runs that are done.
+    pub(crate) fn progress(&self) -> f32 {
+        ratio(self.done.load(Ordering::Relaxed), self.runs) as f32
+    }
+}
+
+impl Drop for AnalysisTask {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Analysis {
+    /// `progress` gets the number of finished runs now and then, returning false stops the
+    /// analysis without a result.
+    pub(crate) fn run(
+        pool: &[&CardRow],
+        options: &AnalysisOptions,
+        rng: &mut impl Rng,
+        mut progress: impl FnMut(usize) -> bool,
+    ) -> Option<Self> {
+        let AnalysisOptions {
+            size,
+            free_cell,
+            weighted,
+            ref quotas,
+            runs,
+        } = *options;
+        let cells: Vec<usize> = size
+            .cells()
+            .into_iter()
+            .filter(|idx| Some(*idx) != free_cell)
+            .collect();
+        let lines = size.lines();
+
+        let mut counts = vec![0_usize; pool.len()];
+        let mut failures = 0;
+        let mut line_weights: Vec<f64> = vec![];
+        let mut spread_total = 0.0;
+
+        let step = (runs / 100).max(1);
+
+        for run in 0..runs {
+            if run % step == 0 && !progress(run) {
+                return None;
+            }
+
+            let Ok(picked) = pick(pool, cells.len(), weighted, quotas, rng) else {
+                failures += 1;
+                continue;
+            };
+
+            let mut weights = [0.0_f64; BOARD_CELLS];
+            for (cell, idx) in cells.iter().zip(picked) {
+                counts[idx] += 1;
+                weights[*cell] = pool[idx].2;
+            }
+
+            let board_lines: Vec<f64> = lines
+                .iter()
+                .map(|(_, line)| line.iter().map(|cell| weights[*cell]).sum())
+                .collect();
+            let max = board_lines.iter().copied().fold(f64::MIN, f64::max);
+            let min = board_lines.iter().copied().fold(f64::MAX, f64::min);
+            spread_total += max - min;
+            line_weights.extend(board_lines);
+        }
+
+        let mut categories: BTreeMap<String, usize> = BTreeMap::new();
+        for (card, count) in pool.iter().zip(counts.iter()) {
+            *categories.entry(card.0.to_owned()).or_default() += count;
+        }
+
+        let successes = runs - failures;
+        let line_mean = mean(&line_weights);
+        let line_variance = mean(
+            &line_weights
+                .iter()
+                .map(|weight| (weight - line_mean).powi(2))
+                .collect::<Vec<f64>>(),
+        );
+
+        let mut cards: Vec<CardFrequency> = pool
+            .iter()
+            .zip(counts)
+            .map(|(card, count)| CardFrequency {
+                category: card.0.to_owned(),
+                text: card.1.to_owned(),
+                weight: card.2,
+                count,
+            })
+            .collect();
+        cards.sort_by_key(|card| std::cmp::Reverse(card.count));
+
+        progress(runs);
+
+        Some(Self {
+            runs,
+            failures,
+            cells: cells.len(),
+            cards,
+            categories,
+            line_mean,
+            line_variance,
+            line_spread: if successes > 0 {
+                spread_total / successes as f64
+            } else {
+                0.0
+            },
+        })
+    }
+
+    pub(crate) fn successes(&self) -> usize {
+        self.runs - self.failures
+    }
+
+    pub(crate) fn failure_rate(&self) -> f64 {
+        ratio(self.failures, self.runs)
+    }
+
+    /// Chance of a card to appear on a successfully generated board.
+    pub(crate) fn card_frequency(&self, card: &CardFrequency) -> f64 {
+        ratio(card.count, self.successes())
+    }
+
+    /// Share of the board cells taken by the category.
+    pub(crate) fn category_frequency(&self, count: usize) -> f64 {
+        ratio(count, self.successes() * self.cells)
+    }
+
+    pub(crate) fn export(&self, path: &Path) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        for (text, value) in [
+            ("runs", self.runs as f64),
+            ("failure rate", self.failure_rate()),
+            ("line mean", self.line_mean),
+            ("line variance", self.line_variance),
+            ("line spread", self.line_spread),
+        ] {
+            writer.serialize(AnalysisRecord {
+                section: "summary",
+                category: "",
+                text,
+                weight: None,
+                count: self.runs,
+                frequency: value,
+            })?;
+        }
+
+        for (category, count) in self.categories.iter() {
+            writer.serialize(AnalysisRecord {
+                section: "category",
+                category,
+                text: "",
+                weight: None,
+                count: *count,
+                frequency: self.category_frequency(*count),
+            })?;
+        }
+
+        for card in self.cards.iter() {
+            writer.serialize(AnalysisRecord {
+                section: "card",
+                category: &card.category,
+                text: &card.text,
+                weight: Some(card.weight),
+                count: card.count,
+                frequency: self.card_frequency(card),
+            })?;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn options(runs: usize) -> AnalysisOptions {
+        AnalysisOptions {
+            size: FieldSize::Three,
+            free_cell: None,
+            weighted: false,
+            quotas: BTreeMap::new(),
+            runs,
+        }
+    }
+
+    #[test]
+    fn runs_report_progress_and_stop() {
+        let cards = (0..12)
+            .map(|i| CardRow("Common".into(), format!("Goal {}", i), 1.0, true))
+            .collect::<Vec<_>>();
+        let pool = cards.iter().collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let mut reported = vec![];
+        let analysis = Analysis::run(&pool, &options(1000), &mut rng, |done| {
+            reported.push(done);
+            true
+        })
+        .unwrap();
+
+        assert_eq!(analysis.successes(), 1000);
+        assert_eq!(
+            analysis.cards.iter().map(|card| card.count).sum::<usize>(),
+            9000
+        );
+        assert_eq!(reported.len(), 101);
+        assert_eq!(reported.last(), Some(&1000));
+
+        let stopped = Analysis::run(&pool, &options(1000), &mut rng, |done| done < 500);
+        assert!(stopped.is_none());
+    }
+}
@@ -4,101 +4,114 @@
 //! Would I be willing to get help? Yes.
 
 use itertools::Itertools;
-use rand::{
-    seq::{IteratorRandom, SliceRandom},
-    thread_rng,
-};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 use std::{
     borrow::Cow,
-    env, fmt,
+    collections::{BTreeMap, HashMap},
+    env,
     fs::File,
-    io::{BufReader, BufWriter},
-    path::PathBuf,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::mpsc,
     time::Duration,
 };
-use weighted_rand::builder::*;
 
 use egui::{FontFamily, FontId, TextStyle, Vec2};
 use egui_data_table::RowViewer;
 use egui_notify::{Anchor, Toast, Toasts};
 use rfd::FileDialog;
 
-#[derive(PartialEq, Eq)]
+#[cfg(feature = "bingosync")]
+use crate::bingosync::{self, RoomClient, RoomSettings, RoomWatch, WatchMessage, WatchSettings};
+#[cfg(feature = "sqlite")]
+use crate::store::{CardStore, DatabaseView};
+use crate::{
+    analysis::{Analysis, AnalysisOptions, AnalysisTask},
+    board::{generate, FieldSize, Transform, BOARD_CELLS, CENTER_CELL},
+    card::CardRow,
+    database::{self, CardChange, Changes, DatabaseSettings, FileLink, NamedDatabase},
+    export::{self, CopyFormat, ExportOptions},
+    import::{self, ColumnMapping, CsvMapping, Sheet},
+    pdf::{self, PdfOptions, PER_PAGE},
+    playtest::{format_duration, Playtest, Team},
+    project::{self, Project, SavedBoard},
+    render,
+    search::{self, Query, SearchSettings},
+    srl,
+    stats::{needed_cards, CategoryStats, DatabaseStats},
+    validate::BoardReport,
+};
+
+#[derive(Default, PartialEq, Eq)]
 enum MainPanel {
+    #[default]
     Board,
     Database,
+    Statistics,
 }
 
-impl Default for MainPanel {
-    fn default() -> Self {
-        Self::Board
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum FieldSize {
-    Three = 3,
-    Four,
-    Five,
-}
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct CardViewer {
+    search: SearchSettings,
 
-impl Default for FieldSize {
-    fn default() -> Self {
-        Self::Five
-    }
-}
+    /// Why the search didn't parse, nothing is shown until it is fixed.
+    #[serde(skip)]
+    search_error: Option<String>,
 
-impl fmt::Display for FieldSize {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{:?}", self)
-    }
-}
+    /// Enabled and total card counts for the column header, updated every frame.
+    #[serde(skip)]
+    enabled_count: (usize, usize),
 
-struct CardViewer {
-    filter: String,
+    /// Category picked on the Board panel and the weight of its enabled cards, for the chance column.
+    #[serde(skip)]
+    pool: (String, f64),
 }
 
-impl Default for CardViewer {
-    fn default() -> Self {
-        Self {
-            filter: Default::default(),
-        }
+impl CardViewer {
+    fn in_pool(category: &str, row: &CardRow) -> bool {
+        row.3 && (category == "All" || row.0 == category)
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-struct CardRow(
-    #[serde(rename = "category")] String,
-    #[serde(rename = "text")] String,
-    #[serde(rename = "weight")] f64,
-    #[serde(rename = "enabled")] bool,
-);
-
 impl RowViewer<CardRow> for CardViewer {
     fn num_columns(&mut self) -> usize {
-        4
+        5
     }
 
     fn column_name(&mut self, column: usize) -> Cow<'static, str> {
-        ["Category", "Text", "Weight", "Enabled"][column].into()
+        if column == 3 {
+            let (enabled, total) = self.enabled_count;
+            return format!("Enabled ({}/{})", enabled, total).into();
+        }
+
+        if column == 4 {
+            return format!("Chance ({})", self.pool.0).into();
+        }
+
+        ["Category", "Text", "Weight"][column].into()
     }
 
-    fn is_sortable_column(&mut self, column: usize) -> bool {
-        [true, true, true, false][column]
+    fn is_sortable_column(&mut self, _column: usize) -> bool {
+        true
     }
 
-    fn create_cell_comparator(&mut self) -> fn(&CardRow, &CardRow, usize) -> std::cmp::Ordering {
-        fn cmp(row_l: &CardRow, row_r: &CardRow, column: usize) -> std::cmp::Ordering {
-            match column {
-                0 => row_l.0.cmp(&row_r.0),
-                1 => row_l.1.cmp(&row_r.1),
-                2 => row_l.2.partial_cmp(&row_r.2).unwrap(),
-                3 => unreachable!(),
-                _ => unreachable!(),
-            }
+    fn create_cell_comparator(
+        &mut self,
+    ) -> impl Fn(&CardRow, &CardRow, usize) -> std::cmp::Ordering {
+        let category = self.pool.0.to_owned();
+
+        move |row_l: &CardRow, row_r: &CardRow, column: usize| match column {
+            0 => row_l.0.cmp(&row_r.0),
+            1 => row_l.1.cmp(&row_r.1),
+            2 => row_l.2.total_cmp(&row_r.2),
+            3 => row_l.3.cmp(&row_r.3),
+            // Cards outside of the pool have no chance at all.
+            4 => (Self::in_pool(&category, row_l), row_l.2)
+                .partial_cmp(&(Self::in_pool(&category, row_r), row_r.2))
+                .unwrap_or(std::cmp::Ordering::Equal),
+            _ => unreachable!(),
         }
-
-        cmp
     }
 
     fn new_empty_row(&mut self) -> CardRow {
@@ -111,6 +124,7 @@ impl RowViewer<CardRow> for CardViewer {
             1 => dst.1 = src.1.clone(),
             2 => dst.2 = src.2,
             3 => dst.3 = src.3,
+            4 => {}
             _ => unreachable!(),
         }
     }
@@ -120,17 +134,43 @@ impl RowViewer<CardRow> for CardViewer {
             0 => ui.label(&row.0),
             1 => ui.label(&row.1),
             2 => ui.label(format!("{}", &row.2)),
+            // Read only, clicks are handled by `on_cell_view_response`.
             3 => ui.checkbox(&mut { row.3 }, ""),
+            4 => {
+                let (category, total) = &self.pool;
+                if Self::in_pool(category, row) && *total > 0.0 {
+                    ui.label(format!("{:.2}%", row.2.max(0.0) / total * 100.0))
+                } else {
+                    ui.label("-")
+                }
+            }
             _ => unreachable!(),
         };
     }
 
+    /// Clicking the Enabled cell toggles it as a regular, undoable edit.
+    fn on_cell_view_response(
+        &mut self,
+        row: &CardRow,
+        column: usize,
+        resp: &egui::Response,
+    ) -> Option<Box<CardRow>> {
+        (column == 3 && resp.clicked()).then(|| {
+            let mut row = row.clone();
+            row.3 = !row.3;
+            Box::new(row)
+        })
+    }
+
     fn row_filter_hash(&mut self) -> &impl std::hash::Hash {
-        &self.filter
+        &self.search
     }
 
     fn create_row_filter(&mut self) -> impl Fn(&CardRow) -> bool {
-        |r| r.1.contains(&self.filter)
+        let query = Query::parse(&self.search);
+        self.search_error = query.as_ref().err().map(|err| err.to_string());
+
+        move |r| query.as_ref().is_ok_and(|query| query.matches(r))
     }
 
     fn show_cell_editor(
@@ -149,16 +189,264 @@ impl RowViewer<CardRow> for CardViewer {
             }
             2 => ui.add(
                 egui::DragValue::new(&mut row.2)
-                    .clamp_range(0.0..=255.0)
+                    .clamp_range(0.0..=f64::MAX)
                     .speed(1.0),
             ),
             3 => ui.checkbox(&mut row.3, ""),
+            // Computed from the weights.
+            4 => return None,
             _ => unreachable!(),
         }
         .into()
     }
 }
 
+#[cfg(feature = "bingosync")]
+struct RoomWindow {
+    open: bool,
+    server: String,
+    settings: RoomSettings,
+    pending: Option<mpsc::Receiver<anyhow::Result<String>>>,
+    url: Option<String>,
+}
+
+#[cfg(feature = "bingosync")]
+impl Default for RoomWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            server: String::from(bingosync::DEFAULT_URL),
+            settings: RoomSettings::default(),
+            pending: None,
+            url: None,
+        }
+    }
+}
+
+#[cfg(feature = "bingosync")]
+struct WatchWindow {
+    open: bool,
+    room: String,
+    passphrase: String,
+    nickname: String,
+    log_path: Option<PathBuf>,
+    watch: Option<RoomWatch>,
+}
+
+#[cfg(feature = "bingosync")]
+impl Default for WatchWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            room: String::from(""),
+            passphrase: String::from(""),
+            nickname: String::from("bingosync-gen"),
+            log_path: None,
+            watch: None,
+        }
+    }
+}
+
+/// CSV file behind the import wizard, kept to read it again with another delimiter.
+struct CsvSource {
+    key: String,
+    content: String,
+    delimiter: u8,
+}
+
+/// Column mapping step for imports that don't have the card layout.
+struct ImportWizard {
+    sheets: Vec<Sheet>,
+    selected: Vec<bool>,
+    mapping: ColumnMapping,
+    sheet_as_category: bool,
+    replace: bool,
+    csv: Option<CsvSource>,
+}
+
+impl ImportWizard {
+    fn new(sheets: Vec<Sheet>, replace: bool) -> Self {
+        Self {
+            mapping: ColumnMapping::guess(sheets.first().map_or(&[], |sheet| &sheet.headers)),
+            selected: vec![true; sheets.len()],
+            sheets,
+            sheet_as_category: false,
+            replace,
+            csv: None,
+        }
+    }
+
+    /// Headers of the first selected sheet, the rest are expected to have the same layout.
+    fn headers(&self) -> &[String] {
+        self.sheets
+            .iter()
+            .zip(self.selected.iter())
+            .find(|(_, selected)| **selected)
+            .map_or(&[], |(sheet, _)| &sheet.headers)
+    }
+
+    fn cards(&self) -> Vec<CardRow> {
+        self.sheets
+            .iter()
+            .zip(self.selected.iter())
+            .filter(|(_, selected)| **selected)
+            .flat_map(|(sheet, _)| {
+                let category = self.sheet_as_category.then_some(sheet.name.as_str());
+                sheet
+                    .rows
+                    .iter()
+                    .filter_map(move |row| self.mapping.card(row, category))
+            })
+            .collect()
+    }
+}
+
+/// Window title, also names the directory the app state is kept in.
+pub const APP_NAME: &str = "BingoSync JSON Generator";
+
+/// Cards of another version of the database, compared against the current one.
+struct CompareView {
+    name: String,
+    incoming: Vec<CardRow>,
+    changes: Vec<CardChange>,
+    selected: Vec<bool>,
+}
+
+impl CompareView {
+    fn new(name: String, incoming: Vec<CardRow>, current: &[CardRow]) -> Self {
+        let mut view = Self {
+            name,
+            incoming,
+            changes: vec![],
+            selected: vec![],
+        };
+        view.refresh(current);
+        view
+    }
+
+    fn refresh(&mut self, current: &[CardRow]) {
+        self.changes = database::diff(current, &self.incoming);
+        self.selected = vec![true; self.changes.len()];
+    }
+}
+
+/// Values typed into the `Bulk Edit` menu.
+struct BulkEdit {
+    category: String,
+    weight: f64,
+    scale: f64,
+    /// Sum of the enabled weights after normalizing.
+    normalize_total: f64,
+}
+
+impl Default for BulkEdit {
+    fn default() -> Self {
+        Self {
+            category: String::from(""),
+            weight: 1.0,
+            scale: 2.0,
+            normalize_total: 100.0,
+        }
+    }
+}
+
+enum BulkAction {
+    SetCategory(String),
+    SetWeight(f64),
+    ScaleWeight(f64),
+    SetEnabled(bool),
+    ToggleEnabled,
+    /// Scales weights so the enabled cards add up to `total`, for each category on its own
+    /// or for all of them together.
+    Normalize {
+        total: f64,
+        per_category: bool,
+    },
+    Duplicate,
+    Delete,
+}
+
+impl BulkAction {
+    fn label(&self) -> &'static str {
+        match self {
+            BulkAction::SetCategory(_) => "Set Category",
+            BulkAction::SetWeight(_) => "Set Weight",
+            BulkAction::ScaleWeight(_) => "Scale Weight",
+            BulkAction::SetEnabled(true) => "Enable",
+            BulkAction::SetEnabled(false) => "Disable",
+            BulkAction::ToggleEnabled => "Toggle",
+            BulkAction::Normalize { .. } => "Normalize",
+            BulkAction::Duplicate => "Duplicate",
+            BulkAction::Delete => "Delete",
+        }
+    }
+
+    /// Applies the action to the cards for which `selected` is true, returns how many there were.
+    fn apply(&self, cards: &mut Vec<CardRow>, selected: impl Fn(&CardRow) -> bool) -> usize {
+        let before = cards.len();
+        let mut count = 0;
+
+        match self {
+            BulkAction::Delete => {
+                cards.retain(|card| !selected(card));
+                return before - cards.len();
+            }
+            BulkAction::Duplicate => {
+                *cards = std::mem::take(cards)
+                    .into_iter()
+                    .flat_map(|card| {
+                        let copy = selected(&card).then(|| card.clone());
+                        std::iter::once(card).chain(copy)
+                    })
+                    .collect();
+                return cards.len() - before;
+            }
+            BulkAction::Normalize {
+                total,
+                per_category,
+            } => {
+                let mut sums: HashMap<&str, f64> = HashMap::new();
+                for card in cards.iter().filter(|card| card.3 && selected(card)) {
+                    let group = if *per_category { card.0.as_str() } else { "" };
+                    *sums.entry(group).or_default() += card.2.max(0.0);
+                }
+                let factors = sums
+                    .into_iter()
+                    .filter(|(_, sum)| *sum > 0.0)
+                    .map(|(group, sum)| (group.to_owned(), total / sum))
+                    .collect::<HashMap<String, f64>>();
+
+                for card in cards.iter_mut().filter(|card| selected(card)) {
+                    let group = if *per_category { card.0.as_str() } else { "" };
+                    if let Some(factor) = factors.get(group) {
+                        card.2 *= factor;
+                        count += 1;
+                    }
+                }
+
+                return count;
+            }
+            _ => {}
+        }
+
+        for card in cards.iter_mut().filter(|card| selected(card)) {
+            match self {
+                BulkAction::SetCategory(category) => card.0 = category.to_owned(),
+                BulkAction::SetWeight(weight) => card.2 = *weight,
+                BulkAction::ScaleWeight(scale) => card.2 = (card.2 * scale).max(0.0),
+                BulkAction::SetEnabled(enabled) => card.3 = *enabled,
+                BulkAction::ToggleEnabled => card.3 = !card.3,
+                BulkAction::Duplicate | BulkAction::Delete | BulkAction::Normalize { .. } => {
+                    unreachable!()
+                }
+            }
+            count += 1;
+        }
+
+        count
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct BingoSyncGen {
@@ -166,7 +454,7 @@ pub struct BingoSyncGen {
     selected_panel: MainPanel,
 
     #[serde(skip)]
-    board: [Box<String>; 25],
+    board: [String; BOARD_CELLS],
 
     #[serde(skip)]
     generated: String,
@@ -183,15 +471,132 @@ pub struct BingoSyncGen {
     #[serde(skip)]
     field_size: FieldSize,
 
+    #[serde(skip)]
+    show_validation: bool,
+
+    #[serde(skip)]
+    show_analysis: bool,
+
+    #[serde(skip)]
+    playtest: Option<Playtest>,
+
+    #[serde(skip)]
+    import_wizard: Option<ImportWizard>,
+
+    #[cfg(feature = "bingosync")]
+    #[serde(skip)]
+    room: RoomWindow,
+
+    #[cfg(feature = "bingosync")]
+    #[serde(skip)]
+    watch: WatchWindow,
+
+    #[serde(skip)]
+    analysis_runs: usize,
+
+    #[serde(skip)]
+    analysis_weighted: bool,
+
+    #[serde(skip)]
+    analysis: Option<Analysis>,
+
+    #[serde(skip)]
+    analysis_task: Option<AnalysisTask>,
+
+    #[serde(skip)]
+    show_pdf: bool,
+
+    pdf_options: PdfOptions,
+
+    free_space: bool,
+
+    free_space_text: String,
+
+    /// Category quotas of the active database.
+    #[serde(skip)]
+    quotas: BTreeMap<String, usize>,
+
+    export_options: ExportOptions,
+
+    /// Delimiter and columns picked for CSV files, by path.
+    csv_mappings: HashMap<String, CsvMapping>,
+
+    /// Cards saved before named databases existed, moved into the first database on load.
+    #[serde(skip_serializing)]
     card_table_data: Vec<CardRow>,
 
+    /// Every database, the cards of the active one live in `card_table` while it is selected.
+    ///
+    /// Empty in states saved before named databases existed, unlike `Default`.
+    #[serde(default)]
+    databases: Vec<NamedDatabase>,
+
+    active_database: usize,
+
     #[serde(skip)]
-    card_table: egui_data_table::DataTable<CardRow>,
+    delete_database: bool,
+
+    /// Name field of the active database, it is checked before the database is renamed.
+    #[serde(skip)]
+    database_name: String,
+
+    #[cfg(feature = "sqlite")]
+    #[serde(skip)]
+    store: Option<CardStore>,
+
+    #[serde(skip)]
+    compare: Option<CompareView>,
+
+    #[serde(skip)]
+    bulk_edit: BulkEdit,
+
+    /// Bulk action waiting for confirmation, with the number of cards it applies to.
+    #[serde(skip)]
+    pending_bulk: Option<(BulkAction, usize)>,
+
+    #[serde(skip)]
+    link: Option<FileLink>,
+
+    /// Linked files by database name, they are local to this app so projects and the card
+    /// store don't have them.
+    #[serde(default)]
+    links: BTreeMap<String, FileLink>,
+
+    #[serde(skip)]
+    link_checked: f64,
+
+    /// Cards read from the linked file while there were local edits.
+    #[serde(skip)]
+    link_conflict: Option<Vec<CardRow>>,
+
+    #[serde(skip)]
+    project_path: Option<PathBuf>,
+
+    /// Project as it was last opened or saved, `None` when no project is open.
+    #[serde(skip)]
+    project_saved: Option<Project>,
+
+    /// Project change waiting for confirmation, with what it would throw away.
+    #[serde(skip)]
+    pending_project: Option<(ProjectAction, Vec<String>)>,
+
+    saved_boards: Vec<SavedBoard>,
 
     #[serde(skip)]
+    window_title: String,
+
+    #[serde(skip)]
+    card_table: egui_data_table::DataTable<CardRow>,
+
+    /// Kept for the search settings.
     card_viewer: CardViewer,
 }
 
+enum ProjectAction {
+    New,
+    Open(PathBuf, Box<Project>),
+}
+
 #[derive(serde::Deserialize, serde::Serialize)]
 struct BingoCard {
     name: String,
@@ -201,13 +606,50 @@ impl Default for BingoSyncGen {
     fn default() -> Self {
         Self {
             selected_panel: MainPanel::default(),
-            board: core::array::from_fn(|_idx| Box::new(String::from(""))),
+            board: core::array::from_fn(|_idx| String::from("")),
             generated: String::from(""),
             toasts: Toasts::default().with_anchor(Anchor::BottomRight),
             save_path: env::current_dir().unwrap(),
             category_select: String::from("All"),
             field_size: FieldSize::default(),
+            show_validation: false,
+            show_analysis: false,
+            playtest: None,
+            import_wizard: None,
+            #[cfg(feature = "bingosync")]
+            room: RoomWindow::default(),
+            #[cfg(feature = "bingosync")]
+            watch: WatchWindow::default(),
+            analysis_runs: 1000,
+            analysis_weighted: false,
+            analysis: None,
+            analysis_task: None,
+            show_pdf: false,
+            pdf_options: PdfOptions::default(),
+            free_space: false,
+            free_space_text: String::from("FREE"),
+            quotas: BTreeMap::new(),
+            export_options: ExportOptions::default(),
+            csv_mappings: Default::default(),
             card_table_data: Default::default(),
+            databases: vec![NamedDatabase::default()],
+            active_database: 0,
+            delete_database: false,
+            database_name: NamedDatabase::default().name,
+            #[cfg(feature = "sqlite")]
+            store: None,
+            compare: None,
+            bulk_edit: BulkEdit::default(),
+            pending_bulk: None,
+            link: None,
+            links: BTreeMap::new(),
+            link_checked: 0.0,
+            link_conflict: None,
+            project_path: None,
+            project_saved: None,
+            pending_project: None,
+            saved_boards: vec![],
+            window_title: String::from(""),
             card_table: Default::default(),
             card_viewer: CardViewer::default(),
         }
@@ -242,6 +684,126 @@ fn configure_text_styles(ctx: &egui::Context) {
     ctx.set_style(style);
 }
 
+fn toast_cb(t: &mut Toast) {
+    t.set_closable(true)
+        .set_duration(Some(Duration::from_millis((1000. * 3.5) as u64)));
+}
+
+fn show_report(ui: &mut egui::Ui, report: &BoardReport) {
+    ui.label(egui::RichText::new("Lines").text_style(heading3()));
+    egui::Grid::new("line_stats").striped(true).show(ui, |ui| {
+        for line in report.lines.iter() {
+            ui.label(&line.name);
+            ui.label(format!("{}", line.weight));
+            ui.end_row();
+        }
+    });
+    if let itertools::MinMaxResult::MinMax(min, max) = report
+        .lines
+        .iter()
+        .map(|line| line.weight)
+        .minmax_by(f64::total_cmp)
+    {
+        ui.label(format!("Spread: {}", max - min));
+    }
+
+    ui.separator();
+    ui.label(egui::RichText::new("Categories").text_style(heading3()));
+    egui::Grid::new("category_stats")
+        .striped(true)
+        .show(ui, |ui| {
+            for (category, count) in report.categories.iter() {
+                ui.label(if category.is_empty() {
+                    "(none)"
+                } else {
+                    category
+                });
+                ui.label(format!("{}", count));
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+    ui.label(egui::RichText::new("Warnings").text_style(heading3()));
+    let error_color = ui.visuals().error_fg_color;
+    if report.warnings.is_empty() {
+        ui.label("None");
+    }
+    if report.empty > 0 {
+        ui.colored_label(error_color, format!("Empty cells: {}", report.empty));
+    }
+    for text in report.duplicates.iter() {
+        ui.colored_label(error_color, format!("Duplicate: {}", text));
+    }
+    for text in report.missing.iter() {
+        ui.colored_label(error_color, format!("Not in database: {}", text));
+    }
+}
+
+fn show_fit(ui: &mut egui::Ui, stats: &CategoryStats, needed: usize) {
+    match stats.fits(needed) {
+        (true, true) => ui.colored_label(egui::Color32::GREEN, "✔"),
+        (true, false) => ui
+            .colored_label(egui::Color32::YELLOW, "⚠")
+            .on_hover_text(format!(
+                "Needs {} cards with weight above 0 for W. Randomize",
+                needed
+            )),
+        _ => ui
+            .colored_label(egui::Color32::RED, "✖")
+            .on_hover_text(format!("Needs {} enabled cards", needed)),
+    };
+}
+
+fn show_analysis_result(ui: &mut egui::Ui, analysis: &Analysis) {
+    ui.label(format!(
+        "Runs: {}, failed: {:.1}%",
+        analysis.runs,
+        analysis.failure_rate() * 100.0
+    ));
+    ui.label(format!(
+        "Line weight mean: {:.2}, variance: {:.2}, avg. spread: {:.2}",
+        analysis.line_mean, analysis.line_variance, analysis.line_spread
+    ));
+
+    ui.separator();
+    ui.label(egui::RichText::new("Categories").text_style(heading3()));
+    egui::Grid::new("analysis_categories")
+        .striped(true)
+        .show(ui, |ui| {
+            for (category, count) in analysis.categories.iter() {
+                ui.label(if category.is_empty() {
+                    "(none)"
+                } else {
+                    category
+                });
+                ui.label(format!(
+                    "{:.1}%",
+                    analysis.category_frequency(*count) * 100.0
+                ));
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+    ui.label(egui::RichText::new("Cards").text_style(heading3()));
+    egui::ScrollArea::vertical()
+        .max_height(320.0)
+        .show(ui, |ui| {
+            egui::Grid::new("analysis_cards")
+                .striped(true)
+                .show(ui, |ui| {
+                    for card in analysis.cards.iter() {
+                        ui.label(&card.category);
+                        ui.label(&card.text);
+                        ui.label(format!("{}", card.weight));
+                        ui.label(format!("{:.1}%", analysis.card_frequency(card) * 100.0));
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
 impl BingoSyncGen {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         configure_text_styles(&cc.egui_ctx);
@@ -250,411 +812,2359 @@ impl BingoSyncGen {
             let mut value: BingoSyncGen =
                 eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
 
-            value.card_table.extend(value.card_table_data.clone());
+            value.migrate_cards();
+            #[cfg(feature = "sqlite")]
+            if let Err(err) = value.open_store() {
+                value.toasts.dismiss_all_toasts();
+                toast_cb(value.toasts.error(err.to_string()));
+            }
+            value.unique_database_names();
+            value.active_database = value.active_database.min(value.databases.len() - 1);
+            value.load_database(value.active_database);
 
             return value;
         }
 
         Default::default()
     }
-}
 
-impl eframe::App for BingoSyncGen {
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        self.card_table_data.clear();
-        self.card_table_data
-            .extend(self.card_table.iter().map(|item| item.to_owned()));
+    /// Moves cards saved before named databases existed into a database of their own.
+    fn migrate_cards(&mut self) {
+        if self.databases.is_empty() || !self.card_table_data.is_empty() {
+            let name = self.unique_database_name("Default");
 
-        eframe::set_value(storage, eframe::APP_KEY, self);
+            self.databases.push(NamedDatabase {
+                name,
+                cards: std::mem::take(&mut self.card_table_data),
+                settings: self.database_settings(),
+            });
+        }
     }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let toast_cb = |t: &mut Toast| {
-            t.set_closable(true)
-                .set_duration(Some(Duration::from_millis((1000. * 3.5) as u64)));
-        };
+    /// Cards in the store replace the persisted ones, an empty store gets them instead.
+    #[cfg(feature = "sqlite")]
+    fn open_store(&mut self) -> anyhow::Result<()> {
+        let dir = eframe::storage_dir(APP_NAME)
+            .ok_or_else(|| anyhow::anyhow!("No storage directory for the card store"))?;
+        std::fs::create_dir_all(&dir)?;
+
+        let mut store = CardStore::open(&dir.join("cards.sqlite"))?;
+        let databases = store.load()?;
+
+        if databases.is_empty() {
+            store.sync(
+                &self
+                    .databases
+                    .iter()
+                    .map(DatabaseView::from)
+                    .collect::<Vec<_>>(),
+            )?;
+        } else {
+            self.databases = databases;
+        }
+        self.store = Some(store);
 
-        self.generated = serde_json::to_string_pretty(&self.board.clone().map(|item| BingoCard {
-            name: if item.is_empty() {
-                " ".to_owned()
-            } else {
-                *item.to_owned()
-            },
-        }))
-        .unwrap();
+        Ok(())
+    }
 
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if self.selected_panel == MainPanel::Board {
-                        if ui.button("Clear Board").clicked() {
-                            for i in 0..self.board.len() {
-                                *self.board[i] = "".to_owned();
-                            }
-                            ui.close_menu();
-                        }
-                    }
-                    if ui.button("Quit").clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    }
-                });
+    /// The active database is read straight from the table, so no cards are copied.
+    #[cfg(feature = "sqlite")]
+    fn sync_store(&mut self) -> anyhow::Result<()> {
+        self.databases[self.active_database].settings = self.database_settings();
 
-                ui.add_space(16.0);
+        let Some(store) = &mut self.store else {
+            return Ok(());
+        };
 
-                ui.horizontal(|ui| {
-                    ui.selectable_value(&mut self.selected_panel, MainPanel::Board, "Bingo Board");
-                    ui.selectable_value(&mut self.selected_panel, MainPanel::Database, "Database");
-                });
+        let databases = self
+            .databases
+            .iter()
+            .enumerate()
+            .map(|(idx, database)| DatabaseView {
+                cards: if idx == self.active_database {
+                    self.card_table.iter().collect()
+                } else {
+                    database.cards.iter().collect()
+                },
+                ..DatabaseView::from(database)
             })
-        });
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            match self.selected_panel {
-                MainPanel::Board => {
-                    egui::Grid::new("bingo_grid")
-                        .num_columns(5)
-                        .spacing([16.0, 16.0])
-                        .show(ui, |ui| {
-                            for c in 0..5 {
-                                for r in 0..5 {
-                                    ui.add_sized(
-                                        Vec2::new(128.0, 128.0),
-                                        egui::TextEdit::multiline(&mut *self.board[c * 5 + r])
-                                            .font(TextStyle::Monospace),
-                                    );
-                                }
-                                ui.end_row();
-                            }
-                        });
+            .collect::<Vec<_>>();
 
-                    ui.separator();
+        store.sync(&databases)
+    }
 
-                    ui.vertical(|ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Generated");
-                            if ui.button("Save").clicked() {
-                                let save_path = FileDialog::new()
-                                    .add_filter("JSON", &["json"])
-                                    .add_filter("Text File", &["txt"])
-                                    .set_directory(&self.save_path)
-                                    .save_file();
-
-                                if let Some(path) = save_path {
-                                    let file = File::create(path).unwrap();
-                                    let mut writer = BufWriter::new(file);
-                                    serde_json::to_writer_pretty(
-                                        &mut writer,
-                                        &self.board.clone().map(|item| BingoCard {
-                                            name: if item.is_empty() {
-                                                " ".to_owned()
-                                            } else {
-                                                *item.to_owned()
-                                            },
-                                        }),
-                                    )
-                                    .unwrap();
-                                }
-                            }
+    /// Puts the cards and settings in use back into the active database.
+    fn store_database(&mut self) {
+        let settings = self.database_settings();
+        let database = &mut self.databases[self.active_database];
 
-                            ui.label("Category".to_owned());
-
-                            egui::ComboBox::from_id_source("category_select")
-                                .selected_text(self.category_select.to_owned())
-                                .show_ui(ui, |ui| {
-                                    for item in [
-                                        vec![String::from("All")],
-                                        self.card_table
-                                            .iter()
-                                            .map(|item| item.0.to_owned())
-                                            .collect::<Vec<String>>()
-                                            .iter()
-                                            .unique()
-                                            .map(|item| item.to_owned())
-                                            .collect::<Vec<String>>(),
-                                    ]
-                                    .concat()
-                                    {
-                                        ui.selectable_value(
-                                            &mut self.category_select,
-                                            item.to_owned(),
-                                            item.to_owned(),
-                                        );
-                                    }
-                                });
+        database.cards = self.card_table.iter().map(|item| item.to_owned()).collect();
+        database.settings = settings;
 
-                            egui::ComboBox::from_id_source("field_select")
-                                .selected_text(match self.field_size {
-                                    FieldSize::Three => String::from("3x3"),
-                                    FieldSize::Four => String::from("4x4"),
-                                    FieldSize::Five => String::from("5x5"),
-                                })
-                                .show_ui(ui, |ui| {
-                                    ui.selectable_value(
-                                        &mut self.field_size,
-                                        FieldSize::Three,
-                                        "3x3",
-                                    );
-                                    ui.selectable_value(
-                                        &mut self.field_size,
-                                        FieldSize::Four,
-                                        "4x4",
-                                    );
-                                    ui.selectable_value(
-                                        &mut self.field_size,
-                                        FieldSize::Five,
-                                        "5x5",
-                                    );
-                                });
+        self.store_link();
+    }
 
-                            if ui.button("Randomize").clicked() {
-                                for i in 0..self.board.len() {
-                                    *self.board[i] = "".to_owned();
+    fn database_settings(&self) -> DatabaseSettings {
+        DatabaseSettings {
+            field_size: self.field_size,
+            free_space: self.free_space,
+            free_space_text: self.free_space_text.to_owned(),
+            quotas: self.quotas.clone(),
+        }
+    }
+
+    /// Puts the link in use back into `links`, and drops links of removed databases.
+    fn store_link(&mut self) {
+        let databases = &self.databases;
+        self.links
+            .retain(|name, _| databases.iter().any(|database| &database.name == name));
+
+        let name = self.databases[self.active_database].name.to_owned();
+        match &self.link {
+            Some(link) => {
+                let mut link = link.clone();
+                // A pending conflict is asked again after a restart, so the file is read again.
+                if self.link_conflict.is_some() {
+                    link.modified = None;
+                }
+                self.links.insert(name, link);
+            }
+            None => {
+                self.links.remove(&name);
+            }
+        }
+    }
+
+    /// Names are all the selector and projects tell databases apart by, states saved before
+    /// that was checked can have empty or repeated ones.
+    fn unique_database_names(&mut self) {
+        for idx in 0..self.databases.len() {
+            let name = self.databases[idx].name.trim().to_owned();
+            let repeated = self.databases[..idx]
+                .iter()
+                .any(|database| database.name == name);
+
+            if name.is_empty() || repeated {
+                let base = if name.is_empty() { "Database" } else { &name };
+                self.databases[idx].name = self.unique_database_name(base);
+            } else {
+                self.databases[idx].name = name;
+            }
+        }
+    }
+
+    /// Renames the active database to `database_name`, empty names and names of other
+    /// databases are refused.
+    fn rename_database(&mut self) -> anyhow::Result<()> {
+        let name = self.database_name.trim().to_owned();
+        let current = self.databases[self.active_database].name.to_owned();
+        self.database_name = current.to_owned();
+
+        if name == current {
+            return Ok(());
+        }
+        if name.is_empty() {
+            anyhow::bail!("Database name can't be empty");
+        }
+        if self.databases.iter().any(|database| database.name == name) {
+            anyhow::bail!("There is already a database named {}", name);
+        }
+
+        self.databases[self.active_database].name = name.to_owned();
+        self.database_name = name;
+
+        Ok(())
+    }
+
+    /// `base`, or `base (n)` if a database already has that name.
+    fn unique_database_name(&self, base: &str) -> String {
+        let taken = |name: &str| self.databases.iter().any(|database| database.name == name);
+
+        if !taken(base) {
+            return base.to_owned();
+        }
+
+        (2..)
+            .map(|number| format!("{} ({})", base, number))
+            .find(|name| !taken(name))
+            .unwrap_or_default()
+    }
+
+    fn load_database(&mut self, idx: usize) {
+        let database = &self.databases[idx];
+
+        self.active_database = idx;
+        self.database_name = database.name.to_owned();
+        self.card_table = Default::default();
+        self.card_table.extend(database.cards.iter().cloned());
+        self.field_size = database.settings.field_size;
+        self.free_space = database.settings.free_space;
+        self.free_space_text = database.settings.free_space_text.to_owned();
+        self.quotas = database.settings.quotas.clone();
+        self.link = self.links.get(&database.name).cloned();
+        self.link_conflict = None;
+        self.category_select = String::from("All");
+    }
+
+    fn switch_database(&mut self, idx: usize) {
+        if idx != self.active_database {
+            self.store_database();
+            self.load_database(idx);
+        }
+    }
+
+    fn new_database(&mut self) {
+        self.store_database();
+
+        let mut number = self.databases.len() + 1;
+        while self
+            .databases
+            .iter()
+            .any(|database| database.name == format!("Database {}", number))
+        {
+            number += 1;
+        }
+
+        self.databases.push(NamedDatabase {
+            name: format!("Database {}", number),
+            ..Default::default()
+        });
+        self.load_database(self.databases.len() - 1);
+    }
+
+    fn remove_database(&mut self) {
+        if self.databases.len() > 1 {
+            self.databases.remove(self.active_database);
+            self.load_database(self.active_database.min(self.databases.len() - 1));
+        }
+    }
+
+    /// The active database with the options and board in use.
+    fn project(&self) -> Project {
+        Project {
+            database: NamedDatabase {
+                name: self.databases[self.active_database].name.to_owned(),
+                cards: self.card_table.iter().map(|item| item.to_owned()).collect(),
+                settings: self.database_settings(),
+            },
+            category: self.category_select.to_owned(),
+            board: self.board.to_vec(),
+            boards: self.saved_boards.clone(),
+            export_options: self.export_options.clone(),
+            pdf_options: self.pdf_options.clone(),
+            analysis_weighted: self.analysis_weighted,
+        }
+    }
+
+    /// Compares in place, so large databases aren't cloned every frame.
+    fn project_dirty(&self) -> bool {
+        let Some(saved) = &self.project_saved else {
+            return false;
+        };
+
+        saved.database.name != self.databases[self.active_database].name
+            || saved.database.settings != self.database_settings()
+            || !saved.database.cards.iter().eq(self.card_table.iter())
+            || saved.category != self.category_select
+            || saved.board != self.board
+            || saved.boards != self.saved_boards
+            || saved.export_options != self.export_options
+            || saved.pdf_options != self.pdf_options
+            || saved.analysis_weighted != self.analysis_weighted
+    }
+
+    /// Runs the action right away, or asks first if it would throw away unsaved changes
+    /// or a database that differs from the one in the project.
+    fn request_project(&mut self, action: ProjectAction) {
+        let mut warnings = vec![];
+
+        if self.project_dirty() {
+            warnings.push(String::from("The open project has unsaved changes."));
+        }
+
+        if let ProjectAction::Open(_, project) = &action {
+            let incoming = &project.database;
+            let existing = self
+                .databases
+                .iter()
+                .position(|database| database.name == incoming.name);
+
+            let replaced = match existing {
+                Some(idx) if idx == self.active_database => {
+                    !incoming.cards.iter().eq(self.card_table.iter())
+                        || incoming.settings != self.database_settings()
+                }
+                Some(idx) => {
+                    incoming.cards != self.databases[idx].cards
+                        || incoming.settings != self.databases[idx].settings
+                }
+                None => false,
+            };
+            if replaced {
+                warnings.push(format!(
+                    "Database \"{}\" will be replaced by the one in the project.",
+                    incoming.name
+                ));
+            }
+        }
+
+        if warnings.is_empty() {
+            self.run_project_action(action);
+        } else {
+            self.pending_project = Some((action, warnings));
+        }
+    }
+
+    fn run_project_action(&mut self, action: ProjectAction) {
+        match action {
+            ProjectAction::New => self.new_project(),
+            ProjectAction::Open(path, project) => self.open_project(path, *project),
+        }
+    }
+
+    /// Starts a project with a new empty database.
+    fn new_project(&mut self) {
+        self.new_database();
+        self.board = core::array::from_fn(|_idx| String::from(""));
+        self.saved_boards.clear();
+        self.project_path = None;
+        self.project_saved = Some(self.project());
+    }
+
+    fn pick_project(&self) -> anyhow::Result<Option<(PathBuf, Project)>> {
+        let Some(path) = FileDialog::new()
+            .add_filter("Project", &[project::EXTENSION])
+            .set_directory(&self.save_path)
+            .pick_file()
+        else {
+            return Ok(None);
+        };
+
+        let project = Project::load(&path)?;
+
+        Ok(Some((path, project)))
+    }
+
+    /// The project database replaces the one with the same name, so opening a project
+    /// again doesn't add copies of it.
+    fn open_project(&mut self, path: PathBuf, project: Project) {
+        self.store_database();
+
+        let idx = match self
+            .databases
+            .iter()
+            .position(|database| database.name == project.database.name)
+        {
+            Some(idx) => {
+                self.databases[idx] = project.database;
+                idx
+            }
+            None => {
+                self.databases.push(project.database);
+                self.unique_database_names();
+                self.databases.len() - 1
+            }
+        };
+        self.load_database(idx);
+
+        self.category_select = project.category;
+        self.board =
+            core::array::from_fn(|idx| project.board.get(idx).cloned().unwrap_or_default());
+        self.saved_boards = project.boards;
+        self.export_options = project.export_options;
+        self.pdf_options = project.pdf_options;
+        self.analysis_weighted = project.analysis_weighted;
+
+        self.project_path = Some(path);
+        self.project_saved = Some(self.project());
+    }
+
+    fn show_project_confirm(&mut self, ctx: &egui::Context) {
+        let Some((action, warnings)) = &self.pending_project else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        egui::Window::new(match action {
+            ProjectAction::New => "New Project",
+            ProjectAction::Open(..) => "Open Project",
+        })
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            for warning in warnings {
+                ui.label(warning);
+            }
+            ui.horizontal(|ui| {
+                confirmed = ui.button("Continue").clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+        if confirmed {
+            if let Some((action, _)) = self.pending_project.take() {
+                self.run_project_action(action);
+            }
+        } else if cancelled {
+            self.pending_project = None;
+        }
+    }
+
+    /// Boards kept in the project, the current one can be added or replaced by them.
+    fn show_saved_boards(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Keep Current Board").clicked() {
+            self.saved_boards.push(SavedBoard {
+                name: format!("Board {}", self.saved_boards.len() + 1),
+                field_size: self.field_size,
+                cells: self.board.to_vec(),
+            });
+        }
+
+        let mut remove = None;
+
+        egui::Grid::new("saved_boards")
+            .num_columns(3)
+            .show(ui, |ui| {
+                for (idx, saved) in self.saved_boards.iter_mut().enumerate() {
+                    ui.add(egui::TextEdit::singleline(&mut saved.name).desired_width(120.0));
+                    if ui.button("Load").clicked() {
+                        self.field_size = saved.field_size;
+                        self.board = core::array::from_fn(|idx| {
+                            saved.cells.get(idx).cloned().unwrap_or_default()
+                        });
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("Remove")
+                        .on_hover_text("Double click to remove")
+                        .double_clicked()
+                    {
+                        remove = Some(idx);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(idx) = remove {
+            self.saved_boards.remove(idx);
+        }
+    }
+
+    fn save_project(&mut self, save_as: bool) -> anyhow::Result<()> {
+        let path = match &self.project_path {
+            Some(path) if !save_as => path.to_owned(),
+            _ => {
+                let Some(path) = FileDialog::new()
+                    .add_filter("Project", &[project::EXTENSION])
+                    .set_directory(&self.save_path)
+                    .set_file_name(format!(
+                        "{}.{}",
+                        self.databases[self.active_database].name,
+                        project::EXTENSION
+                    ))
+                    .save_file()
+                else {
+                    return Ok(());
+                };
+                path
+            }
+        };
+
+        let project = self.project();
+        project.save(&path)?;
+
+        self.project_path = Some(path);
+        self.project_saved = Some(project);
+
+        Ok(())
+    }
+
+    fn update_title(&mut self, ctx: &egui::Context) {
+        let title = match &self.project_saved {
+            Some(_) => format!(
+                "{}{} - {}",
+                self.project_path
+                    .as_ref()
+                    .and_then(|path| path.file_stem())
+                    .map_or(Cow::Borrowed("Untitled"), |name| name.to_string_lossy()),
+                if self.project_dirty() { "*" } else { "" },
+                APP_NAME
+            ),
+            None => APP_NAME.to_owned(),
+        };
+
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.to_owned()));
+            self.window_title = title;
+        }
+    }
+
+    fn database_select(&mut self, ui: &mut egui::Ui, id: &str) {
+        let mut selected = self.active_database;
+
+        egui::ComboBox::from_id_source(id)
+            .selected_text(self.databases[self.active_database].name.to_owned())
+            .show_ui(ui, |ui| {
+                for (idx, database) in self.databases.iter().enumerate() {
+                    ui.selectable_value(&mut selected, idx, &database.name);
+                }
+            });
+
+        self.switch_database(selected);
+    }
+
+    /// Quotas only apply when every category is in the pool.
+    fn board_quotas(&self) -> BTreeMap<String, usize> {
+        if self.category_select == "All" {
+            self.quotas.clone()
+        } else {
+            BTreeMap::new()
+        }
+    }
+
+    fn show_quotas(&mut self, ui: &mut egui::Ui) {
+        ui.label("Least cards of a category on a board, used with All categories");
+
+        let categories = self
+            .card_table
+            .iter()
+            .map(|card| card.0.to_owned())
+            .chain(self.quotas.keys().cloned())
+            .unique()
+            .sorted()
+            .collect::<Vec<String>>();
+
+        egui::Grid::new("quotas").num_columns(2).show(ui, |ui| {
+            for category in categories {
+                let mut quota = self.quotas.get(&category).copied().unwrap_or(0);
+
+                ui.label(&category);
+                if ui
+                    .add(egui::DragValue::new(&mut quota).clamp_range(0..=BOARD_CELLS))
+                    .changed()
+                {
+                    if quota == 0 {
+                        self.quotas.remove(&category);
+                    } else {
+                        self.quotas.insert(category, quota);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        if ui.button("Clear").clicked() {
+            self.quotas.clear();
+        }
+    }
+
+    /// Enabled cards matching the selected category.
+    fn card_pool(&self) -> Vec<&CardRow> {
+        self.card_table
+            .iter()
+            .filter(|i| i.3 && (self.category_select == "All" || i.0 == self.category_select))
+            .collect()
+    }
+
+    /// Board in the BingoSync format, empty cells are kept as whitespace so the site won't drop them.
+    fn bingo_cards(&self) -> [BingoCard; BOARD_CELLS] {
+        self.board.clone().map(|item| BingoCard {
+            name: if item.is_empty() {
+                " ".to_owned()
+            } else {
+                item
+            },
+        })
+    }
+
+    /// Writes the board in the format matching the file extension, JSON by default.
+    fn save_board(&self, path: &Path) -> anyhow::Result<()> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "png" => render::render_png(&self.board, self.field_size, &self.export_options, path),
+            "html" | "htm" => Ok(std::fs::write(
+                path,
+                export::to_html(&self.board, self.field_size, &self.export_options),
+            )?),
+            "md" => Ok(std::fs::write(
+                path,
+                export::to_markdown(&self.board, self.field_size),
+            )?),
+            _ => {
+                let writer = BufWriter::new(File::create(path)?);
+                serde_json::to_writer_pretty(writer, &self.bingo_cards())?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Copy and paste both go through the system clipboard, so a copied board can always be pasted.
+    fn copy_board(&self, format: CopyFormat) -> anyhow::Result<()> {
+        arboard::Clipboard::new()?.set_text(format.format(&self.board, self.field_size))?;
+
+        Ok(())
+    }
+
+    fn paste_board(&mut self) -> anyhow::Result<()> {
+        let text = arboard::Clipboard::new()?.get_text()?;
+        self.paste_text(&text)
+    }
+
+    fn paste_text(&mut self, text: &str) -> anyhow::Result<()> {
+        let (size, board) = export::parse_board(text)?;
+
+        // JSON has the whole board, it may have been copied from a smaller field.
+        if let Some(size) = size {
+            self.field_size = size;
+        }
+        self.board = board;
+
+        Ok(())
+    }
+
+    /// Board index reserved by the free space, if it is in use.
+    fn free_cell(&self) -> Option<usize> {
+        (self.free_space && self.field_size.has_center()).then_some(CENTER_CELL)
+    }
+
+    fn show_playtest(&mut self, ui: &mut egui::Ui) {
+        let free_cell = self.free_cell();
+        let Some(playtest) = &mut self.playtest else {
+            return;
+        };
+        let (size, free_cell) = match playtest.room_board {
+            Some(_) => (FieldSize::Five, None),
+            None => (self.field_size, free_cell),
+        };
+        let cells = size.cells();
+
+        ui.horizontal(|ui| {
+            ui.label("Team");
+            egui::ComboBox::from_id_source("team_select")
+                .selected_text(
+                    egui::RichText::new(playtest.team.name()).color(playtest.team.color()),
+                )
+                .show_ui(ui, |ui| {
+                    for team in Team::ALL {
+                        ui.selectable_value(
+                            &mut playtest.team,
+                            team,
+                            egui::RichText::new(team.name()).color(team.color()),
+                        );
+                    }
+                });
+
+            ui.label(format_duration(playtest.elapsed()));
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+
+            if ui.button("Reset").clicked() {
+                playtest.reset();
+            }
+        });
+
+        egui::Grid::new("play_grid")
+            .num_columns(5)
+            .spacing([16.0, 16.0])
+            .show(ui, |ui| {
+                for c in 0..5 {
+                    for r in 0..5 {
+                        let idx = c * 5 + r;
+                        let (rect, response) =
+                            ui.allocate_exact_size(Vec2::new(128.0, 128.0), egui::Sense::click());
+
+                        if !cells.contains(&idx) {
+                            continue;
+                        }
+
+                        let painter = ui.painter();
+                        let teams = &playtest.marks[idx];
+                        let mut text_color = egui::Color32::WHITE;
+
+                        if Some(idx) == free_cell {
+                            painter.rect_filled(rect, 2.0, ui.visuals().selection.bg_fill);
+                        } else if teams.is_empty() {
+                            painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+                            text_color = ui.visuals().text_color();
+                        } else {
+                            let width = rect.width() / teams.len() as f32;
+                            for (i, team) in teams.iter().enumerate() {
+                                painter.rect_filled(
+                                    egui::Rect::from_min_size(
+                                        rect.min + Vec2::new(width * i as f32, 0.0),
+                                        Vec2::new(width, rect.height()),
+                                    ),
+                                    0.0,
+                                    team.color(),
+                                );
+                            }
+                        }
+                        painter.rect_stroke(rect, 2.0, ui.visuals().widgets.inactive.bg_stroke);
+
+                        let text = playtest
+                            .room_board
+                            .as_ref()
+                            .map_or(&self.board[idx], |board| &board[idx])
+                            .to_owned();
+                        let galley = painter.layout(
+                            text.to_owned(),
+                            TextStyle::Monospace.resolve(ui.style()),
+                            text_color,
+                            rect.width() - 8.0,
+                        );
+                        painter.galley(rect.center() - galley.size() / 2.0, galley, text_color);
+
+                        if response.clicked()
+                            && playtest.room_board.is_none()
+                            && Some(idx) != free_cell
+                        {
+                            playtest.toggle(idx, &text, size, free_cell);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+
+        for team in Team::ALL {
+            if !playtest.marks.iter().any(|marks| marks.contains(&team)) {
+                continue;
+            }
+
+            let marked = cells
+                .iter()
+                .filter(|idx| playtest.is_marked(**idx, team, free_cell))
+                .count();
+            let bingos = playtest.bingos(team, size, free_cell).len();
+
+            ui.colored_label(
+                team.color(),
+                format!(
+                    "{}: {}/{} marked, {} bingo{}{}",
+                    team.name(),
+                    marked,
+                    cells.len(),
+                    bingos,
+                    if bingos == 1 { "" } else { "s" },
+                    if playtest.blackout(team, size, free_cell) {
+                        ", blackout"
+                    } else {
+                        ""
+                    }
+                ),
+            );
+        }
+
+        egui::ScrollArea::vertical()
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for event in playtest.events.iter() {
+                    ui.horizontal(|ui| {
+                        ui.label(format_duration(event.at));
+                        ui.colored_label(event.team.color(), event.team.name());
+                        ui.label(&event.text);
+                    });
+                }
+            });
+    }
+
+    #[cfg(feature = "bingosync")]
+    fn show_room_window(&mut self, ctx: &egui::Context) {
+        if let Some(pending) = &self.room.pending {
+            match pending.try_recv() {
+                Ok(Ok(url)) => {
+                    self.room.url = Some(url);
+                    self.room.pending = None;
+                }
+                Ok(Err(err)) => {
+                    self.toasts.dismiss_all_toasts();
+                    toast_cb(self.toasts.error(err.to_string()));
+                    self.room.pending = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(Duration::from_millis(100));
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.room.pending = None,
+            }
+        }
+
+        let mut open = self.room.open;
+        let mut create = false;
+
+        egui::Window::new("BingoSync Room")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let room = &mut self.room;
+
+                egui::Grid::new("room_settings")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Server");
+                        ui.text_edit_singleline(&mut room.server);
+                        ui.end_row();
+
+                        ui.label("Room Name");
+                        ui.text_edit_singleline(&mut room.settings.name);
+                        ui.end_row();
+
+                        ui.label("Password");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut room.settings.passphrase)
+                                .password(true),
+                        );
+                        ui.end_row();
+
+                        ui.label("Nickname");
+                        ui.text_edit_singleline(&mut room.settings.nickname);
+                        ui.end_row();
+                    });
+
+                ui.checkbox(&mut room.settings.lockout, "Lockout");
+                ui.checkbox(&mut room.settings.hide_card, "Hide Card");
+                ui.checkbox(&mut room.settings.spectator, "Join as Spectator");
+
+                ui.horizontal(|ui| {
+                    create = ui
+                        .add_enabled(room.pending.is_none(), egui::Button::new("Create Room"))
+                        .clicked();
+                    if room.pending.is_some() {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(url) = &room.url {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.hyperlink(url);
+                        if ui.button("Copy").clicked() {
+                            ui.output_mut(|o| o.copied_text = url.to_owned());
+                        }
+                    });
+                }
+            });
+
+        self.room.open = open;
+
+        if create {
+            let (sender, receiver) = mpsc::channel();
+            let server = self.room.server.to_owned();
+            let settings = self.room.settings.clone();
+            let custom_json = serde_json::to_string(&self.bingo_cards()).unwrap();
+            let ctx = ctx.clone();
+
+            std::thread::spawn(move || {
+                let result = RoomClient::new(&server).create_room(&settings, &custom_json);
+                let _ = sender.send(result);
+                ctx.request_repaint();
+            });
+
+            self.room.url = None;
+            self.room.pending = Some(receiver);
+        }
+    }
+
+    #[cfg(feature = "bingosync")]
+    fn show_watch_window(&mut self, ctx: &egui::Context) {
+        while let Some(message) = self.watch.watch.as_ref().and_then(RoomWatch::try_recv) {
+            match message {
+                // The local board and its settings are left alone, the room is shown in play mode.
+                WatchMessage::Board(squares) => {
+                    let mut playtest = Playtest::default();
+                    let mut board: [String; BOARD_CELLS] = Default::default();
+
+                    for square in squares {
+                        if let Some(idx) = square.index() {
+                            playtest.marks[idx] = square
+                                .colors
+                                .split_whitespace()
+                                .filter_map(Team::from_name)
+                                .collect();
+                            board[idx] = square.name;
+                        }
+                    }
+
+                    playtest.room_board = Some(board);
+                    self.playtest = Some(playtest);
+                    self.selected_panel = MainPanel::Board;
+                }
+                WatchMessage::Goal(event) => {
+                    let team = event.player_color.as_deref().and_then(Team::from_name);
+
+                    if let (Some(playtest), Some(team), Some(square)) =
+                        (&mut self.playtest, team, &event.square)
+                    {
+                        if let Some(idx) = square.index() {
+                            playtest.set_mark(
+                                team,
+                                idx,
+                                &square.name,
+                                FieldSize::Five,
+                                None,
+                                !event.remove,
+                            );
+                        }
+                    }
+                }
+                WatchMessage::Error(err) => {
+                    self.toasts.dismiss_all_toasts();
+                    toast_cb(self.toasts.error(err));
+                    self.watch.watch = None;
+                }
+            }
+        }
+
+        let mut open = self.watch.open;
+        let mut connect = false;
+
+        egui::Window::new("Watch Room")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let watch = &mut self.watch;
+                let connected = watch.watch.is_some();
+
+                ui.add_enabled_ui(!connected, |ui| {
+                    egui::Grid::new("watch_settings")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Room URL");
+                            ui.text_edit_singleline(&mut watch.room);
+                            ui.end_row();
+
+                            ui.label("Password");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut watch.passphrase).password(true),
+                            );
+                            ui.end_row();
+
+                            ui.label("Nickname");
+                            ui.text_edit_singleline(&mut watch.nickname);
+                            ui.end_row();
+
+                            ui.label("Log File");
+                            ui.horizontal(|ui| {
+                                if ui.button("Pick").clicked() {
+                                    watch.log_path = FileDialog::new()
+                                        .add_filter("Text File", &["txt", "tsv"])
+                                        .set_directory(&self.save_path)
+                                        .save_file();
+                                }
+                                if let Some(path) = &watch.log_path {
+                                    ui.label(path.display().to_string());
+                                }
+                            });
+                            ui.end_row();
+                        });
+                });
+
+                if connected {
+                    if ui.button("Disconnect").clicked() {
+                        watch.watch = None;
+                    }
+                } else {
+                    connect = ui.button("Connect").clicked();
+                }
+            });
+
+        self.watch.open = open;
+
+        if connect {
+            let room = self.watch.room.trim();
+            // Room URLs point at the site, the API lives on the same host.
+            let server = room
+                .find("/room/")
+                .map_or(bingosync::DEFAULT_URL, |idx| &room[..idx]);
+            let ctx = ctx.clone();
+
+            self.watch.watch = Some(RoomWatch::start(
+                WatchSettings {
+                    server: server.to_owned(),
+                    room: room.to_owned(),
+                    passphrase: self.watch.passphrase.to_owned(),
+                    nickname: self.watch.nickname.to_owned(),
+                    log_path: self.watch.log_path.clone(),
+                    poll_interval: bingosync::POLL_INTERVAL,
+                },
+                move || ctx.request_repaint(),
+            ));
+        }
+    }
+
+    /// Loads a database, either replacing the current one or adding to it.
+    ///
+    /// Spreadsheets go through the import wizard to pick sheets and columns.
+    fn import_database(&mut self, replace: bool) -> anyhow::Result<()> {
+        let path = FileDialog::new()
+            .add_filter(
+                "Database",
+                &[
+                    database::EXTENSIONS.as_slice(),
+                    import::SPREADSHEET_EXTENSIONS.as_slice(),
+                ]
+                .concat(),
+            )
+            .set_directory(&self.save_path)
+            .pick_file();
+
+        if let Some(path) = path {
+            if path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    import::SPREADSHEET_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+                })
+            {
+                self.import_wizard =
+                    Some(ImportWizard::new(import::read_spreadsheet(&path)?, replace));
+                return Ok(());
+            }
+
+            if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+            {
+                return self.import_csv(&path, replace);
+            }
+
+            let data = database::load(&path)?;
+
+            if replace {
+                self.card_table.replace(data);
+            } else {
+                self.card_table.extend(data);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bulk actions work on the rows matching the search, the table doesn't expose its selection.
+    fn bulk_edit(&mut self, action: BulkAction) -> anyhow::Result<()> {
+        let query = Query::parse(&self.card_viewer.search)?;
+        let mut cards = self.card_table.take();
+        let count = action.apply(&mut cards, |card| query.matches(card));
+
+        self.card_table.replace(cards);
+        toast_cb(self.toasts.info(format!("{} cards changed", count)));
+
+        Ok(())
+    }
+
+    fn show_bulk_menu(&mut self, ui: &mut egui::Ui) {
+        let matching = Query::parse(&self.card_viewer.search)
+            .map(|query| {
+                self.card_table
+                    .iter()
+                    .filter(|card| query.matches(card))
+                    .count()
+            })
+            .unwrap_or(0);
+        let mut action = None;
+
+        ui.label(format!("Applies to {} cards matching the search", matching));
+        ui.separator();
+
+        egui::Grid::new("bulk_edit").num_columns(2).show(ui, |ui| {
+            ui.text_edit_singleline(&mut self.bulk_edit.category);
+            if ui.button("Set Category").clicked() {
+                action = Some(BulkAction::SetCategory(self.bulk_edit.category.to_owned()));
+            }
+            ui.end_row();
+
+            ui.add(
+                egui::DragValue::new(&mut self.bulk_edit.weight)
+                    .clamp_range(0.0..=f64::MAX)
+                    .speed(1.0),
+            );
+            if ui.button("Set Weight").clicked() {
+                action = Some(BulkAction::SetWeight(self.bulk_edit.weight));
+            }
+            ui.end_row();
+
+            ui.add(
+                egui::DragValue::new(&mut self.bulk_edit.scale)
+                    .clamp_range(0.0..=100.0)
+                    .speed(0.1)
+                    .prefix("x"),
+            );
+            if ui.button("Scale Weight").clicked() {
+                action = Some(BulkAction::ScaleWeight(self.bulk_edit.scale));
+            }
+            ui.end_row();
+
+            ui.add(
+                egui::DragValue::new(&mut self.bulk_edit.normalize_total)
+                    .clamp_range(0.0..=f64::MAX)
+                    .speed(1.0),
+            );
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Normalize")
+                    .on_hover_text("Scale weights so the enabled cards add up to the total")
+                    .clicked()
+                {
+                    action = Some(BulkAction::Normalize {
+                        total: self.bulk_edit.normalize_total,
+                        per_category: false,
+                    });
+                }
+                if ui
+                    .button("Per Category")
+                    .on_hover_text("Normalize every category to the total on its own")
+                    .clicked()
+                {
+                    action = Some(BulkAction::Normalize {
+                        total: self.bulk_edit.normalize_total,
+                        per_category: true,
+                    });
+                }
+            });
+            ui.end_row();
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("Enable").clicked() {
+                action = Some(BulkAction::SetEnabled(true));
+            }
+            if ui.button("Disable").clicked() {
+                action = Some(BulkAction::SetEnabled(false));
+            }
+            if ui.button("Toggle").clicked() {
+                action = Some(BulkAction::ToggleEnabled);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Duplicate").clicked() {
+                action = Some(BulkAction::Duplicate);
+            }
+            if ui
+                .button("Delete")
+                .on_hover_text("Double click to delete")
+                .double_clicked()
+            {
+                action = Some(BulkAction::Delete);
+            }
+        });
+
+        if let Some(action) = action {
+            self.pending_bulk = Some((action, matching));
+            ui.close_menu();
+        }
+    }
+
+    /// The table can only be changed as a whole from outside, which also clears its undo
+    /// history, so bulk edits ask first.
+    fn show_bulk_confirm(&mut self, ctx: &egui::Context) {
+        let Some((action, matching)) = &self.pending_bulk else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        egui::Window::new(format!("Bulk Edit: {}", action.label()))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Applies to {} cards matching the search.",
+                    matching
+                ));
+                ui.label("Bulk edits can't be undone and clear the undo history of the table.");
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("Continue").clicked();
+                    cancelled = ui.button("Cancel").clicked();
+                });
+            });
+
+        if confirmed {
+            if let Some((action, _)) = self.pending_bulk.take() {
+                if let Err(err) = self.bulk_edit(action) {
+                    self.toasts.dismiss_all_toasts();
+                    toast_cb(self.toasts.error(err.to_string()));
+                }
+            }
+        } else if cancelled {
+            self.pending_bulk = None;
+        }
+    }
+
+    fn show_statistics(&mut self, ui: &mut egui::Ui) {
+        let pool = self.card_pool();
+        let needed = needed_cards(self.field_size, self.free_space);
+        let stats = DatabaseStats::new(self.card_table.iter(), &pool, needed);
+        let sizes = [FieldSize::Three, FieldSize::Four, FieldSize::Five];
+
+        ui.label(format!(
+            "{} cards, {} enabled, {} disabled",
+            stats.all.total,
+            stats.all.enabled,
+            stats.all.total - stats.all.enabled
+        ));
+
+        ui.separator();
+        ui.label(egui::RichText::new("Categories").text_style(heading3()));
+        egui::Grid::new("stats_categories")
+            .striped(true)
+            .num_columns(6)
+            .show(ui, |ui| {
+                ui.strong("Category");
+                ui.strong("Cards");
+                ui.strong("Enabled");
+                for size in sizes {
+                    ui.strong(format!("{0}x{0}", size as usize));
+                }
+                ui.end_row();
+
+                for (category, category_stats) in [(&String::from("All"), &stats.all)]
+                    .into_iter()
+                    .chain(stats.categories.iter())
+                {
+                    ui.label(if category.is_empty() {
+                        "(none)"
+                    } else {
+                        category
+                    });
+                    ui.label(category_stats.total.to_string());
+                    ui.label(category_stats.enabled.to_string());
+                    for size in sizes {
+                        show_fit(ui, category_stats, needed_cards(size, self.free_space));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+        ui.label(egui::RichText::new("Weights").text_style(heading3()));
+        let largest = stats
+            .histogram
+            .iter()
+            .map(|bucket| bucket.count)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        egui::Grid::new("stats_weights").show(ui, |ui| {
+            for bucket in &stats.histogram {
+                ui.label(format!("{:.1} - {:.1}", bucket.min, bucket.max));
+                ui.add(
+                    egui::ProgressBar::new(bucket.count as f32 / largest as f32)
+                        .desired_width(240.0)
+                        .text(bucket.count.to_string()),
+                );
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        ui.label(
+            egui::RichText::new(format!(
+                "Chances in W. Randomize ({}, {} cards per board)",
+                self.category_select, needed
+            ))
+            .text_style(heading3()),
+        );
+        egui_extras::TableBuilder::new(ui)
+            .striped(true)
+            .column(egui_extras::Column::auto())
+            .column(egui_extras::Column::remainder().clip(true))
+            .columns(egui_extras::Column::auto(), 3)
+            .header(20.0, |mut header| {
+                for name in ["Category", "Text", "Weight", "Next Pick", "On Board"] {
+                    header.col(|ui| {
+                        ui.strong(name);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, stats.chances.len(), |mut row| {
+                    let chance = &stats.chances[row.index()];
+                    row.col(|ui| {
+                        ui.label(&chance.category);
+                    });
+                    row.col(|ui| {
+                        ui.label(&chance.text);
+                    });
+                    row.col(|ui| {
+                        ui.label(chance.weight.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}%", chance.draw * 100.0));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.1}%", chance.board * 100.0));
+                    });
+                });
+            });
+    }
+
+    fn open_compare(&mut self) -> anyhow::Result<()> {
+        let Some(path) = FileDialog::new()
+            .add_filter("Database", &database::EXTENSIONS)
+            .set_directory(&self.save_path)
+            .pick_file()
+        else {
+            return Ok(());
+        };
+
+        let current = self.card_table.iter().cloned().collect::<Vec<_>>();
+        self.compare = Some(CompareView::new(
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            database::load(&path)?,
+            &current,
+        ));
+
+        Ok(())
+    }
+
+    fn show_compare(&mut self, ctx: &egui::Context) {
+        let Some(view) = &mut self.compare else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = false;
+
+        egui::Window::new(format!("Compare with {}", view.name))
+            .open(&mut open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                if view.changes.is_empty() {
+                    ui.label("No differences.");
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} changes", view.changes.len()));
+                    if ui.button("Select All").clicked() {
+                        view.selected
+                            .iter_mut()
+                            .for_each(|selected| *selected = true);
+                    }
+                    if ui.button("Select None").clicked() {
+                        view.selected
+                            .iter_mut()
+                            .for_each(|selected| *selected = false);
+                    }
+                    apply = ui.button("Apply Selected").clicked();
+                });
+
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("compare_changes")
+                        .striped(true)
+                        .num_columns(6)
+                        .show(ui, |ui| {
+                            ui.label("");
+                            ui.strong("Change");
+                            ui.strong("Category");
+                            ui.strong("Text");
+                            ui.strong("Weight");
+                            ui.strong("Enabled");
+                            ui.end_row();
+
+                            for (change, selected) in view.changes.iter().zip(&mut view.selected) {
+                                ui.checkbox(selected, "");
+
+                                match change {
+                                    CardChange::Added(card) | CardChange::Removed(card) => {
+                                        if matches!(change, CardChange::Added(_)) {
+                                            ui.colored_label(egui::Color32::GREEN, "Added");
+                                        } else {
+                                            ui.colored_label(egui::Color32::RED, "Removed");
+                                        }
+                                        ui.label(&card.0);
+                                        ui.label(&card.1);
+                                        ui.label(card.2.to_string());
+                                        ui.label(card.3.to_string());
+                                    }
+                                    CardChange::Modified { old, new } => {
+                                        let field = |old: String, new: String| {
+                                            if old == new {
+                                                new
+                                            } else {
+                                                format!("{} → {}", old, new)
+                                            }
+                                        };
+
+                                        ui.colored_label(egui::Color32::YELLOW, "Modified");
+                                        ui.label(field(old.0.to_owned(), new.0.to_owned()));
+                                        ui.label(&new.1);
+                                        ui.label(field(old.2.to_string(), new.2.to_string()));
+                                        ui.label(field(old.3.to_string(), new.3.to_string()));
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if apply {
+            let mut cards = self.card_table.iter().cloned().collect::<Vec<_>>();
+            database::apply(
+                &mut cards,
+                view.changes
+                    .iter()
+                    .zip(&view.selected)
+                    .filter(|(_, selected)| **selected)
+                    .map(|(change, _)| change),
+            );
+
+            view.refresh(&cards);
+            self.card_table.replace(cards);
+        }
+
+        if !open {
+            self.compare = None;
+        }
+    }
+
+    /// Replaces the cards with the file content and keeps them in sync with it.
+    fn link_file(&mut self) -> anyhow::Result<()> {
+        let Some(path) = FileDialog::new()
+            .add_filter("Database", &database::EXTENSIONS)
+            .set_directory(&self.save_path)
+            .pick_file()
+        else {
+            return Ok(());
+        };
+
+        let cards = database::load(&path)?;
+        let mut link = FileLink::new(path, &cards);
+        link.poll()?;
+
+        self.card_table.replace(cards);
+        self.link = Some(link);
+        self.link_conflict = None;
+
+        Ok(())
+    }
+
+    /// Reloads the linked file once a second, local edits are never dropped without asking.
+    fn check_link(&mut self, ctx: &egui::Context) {
+        let Some(link) = &mut self.link else {
+            return;
+        };
+
+        ctx.request_repaint_after(Duration::from_secs(1));
+
+        let now = ctx.input(|i| i.time);
+        if self.link_conflict.is_some() || now - self.link_checked < 1.0 {
+            return;
+        }
+        self.link_checked = now;
+
+        let cards = match link.poll() {
+            Ok(Some(cards)) => cards,
+            Ok(None) => return,
+            Err(err) => {
+                log::warn!("Failed to read {}: {}", link.path.display(), err);
+                return;
+            }
+        };
+
+        if self.card_table.iter().eq(cards.iter()) {
+            link.synced = database::cards_hash(&cards);
+        } else if database::cards_hash(self.card_table.iter()) == link.synced {
+            let local = self.card_table.iter().cloned().collect::<Vec<_>>();
+            let changes = Changes::between(&local, &cards);
+            let name = link
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            link.synced = database::cards_hash(&cards);
+            self.card_table.replace(cards);
+            toast_cb(self.toasts.info(format!("Reloaded {}: {}", name, changes)));
+        } else {
+            self.link_conflict = Some(cards);
+        }
+    }
+
+    fn show_link_conflict(&mut self, ctx: &egui::Context) {
+        let (Some(link), Some(cards)) = (&mut self.link, &self.link_conflict) else {
+            return;
+        };
+
+        let mut reload = false;
+        let mut keep = false;
+        let mut overwrite = false;
+
+        egui::Window::new("Linked File Changed")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} changed on disk while the database has unsaved edits.",
+                    link.path.display()
+                ));
+                ui.label(format!(
+                    "Reloading: {}",
+                    Changes::between(&self.card_table.iter().cloned().collect::<Vec<_>>(), cards)
+                ));
+                ui.horizontal(|ui| {
+                    reload = ui.button("Reload File").clicked();
+                    keep = ui.button("Keep Local").clicked();
+                    overwrite = ui.button("Overwrite File").clicked();
+                });
+            });
+
+        if reload {
+            let cards = self.link_conflict.take().unwrap_or_default();
+            link.synced = database::cards_hash(&cards);
+            self.card_table.replace(cards);
+        } else if keep {
+            // The file version becomes the base, so the prompt comes back only on the next change.
+            let cards = self.link_conflict.take().unwrap_or_default();
+            link.synced = database::cards_hash(&cards);
+        } else if overwrite {
+            let cards = self.card_table.iter().cloned().collect::<Vec<_>>();
+            if let Err(err) = link.write(&cards) {
+                self.toasts.dismiss_all_toasts();
+                toast_cb(self.toasts.error(err.to_string()));
+            }
+            self.link_conflict = None;
+        }
+    }
+
+    /// Files with the usual `category,text,weight,enabled` header are imported right away,
+    /// others go through the import wizard.
+    fn import_csv(&mut self, path: &Path, replace: bool) -> anyhow::Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        let key = path.display().to_string();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let remembered = self.csv_mappings.get(&key).cloned();
+        let delimiter = remembered
+            .as_ref()
+            .map_or_else(|| import::detect_delimiter(&content), |csv| csv.delimiter);
+
+        let mut wizard =
+            ImportWizard::new(vec![import::read_csv(&name, &content, delimiter)?], replace);
+
+        match remembered {
+            Some(csv) => wizard.mapping = csv.mapping(&wizard.sheets[0].headers),
+            None if delimiter == b',' && wizard.mapping.is_complete() => {
+                let data = wizard.cards();
+
+                if replace {
+                    self.card_table.replace(data);
+                } else {
+                    self.card_table.extend(data);
+                }
+
+                return Ok(());
+            }
+            None => {}
+        }
+
+        wizard.csv = Some(CsvSource {
+            key,
+            content,
+            delimiter,
+        });
+        self.import_wizard = Some(wizard);
+
+        Ok(())
+    }
+
+    fn show_import_wizard(&mut self, ctx: &egui::Context) {
+        let Some(wizard) = &mut self.import_wizard else {
+            return;
+        };
+
+        let mut open = true;
+        let mut import = false;
+
+        egui::Window::new("Import").open(&mut open).show(ctx, |ui| {
+            if let Some(csv) = &mut wizard.csv {
+                let delimiter = csv.delimiter;
+
+                ui.horizontal(|ui| {
+                    ui.label("Delimiter");
+                    egui::ComboBox::from_id_source("import_delimiter")
+                        .selected_text(
+                            import::DELIMITERS
+                                .iter()
+                                .find(|(value, _)| *value == csv.delimiter)
+                                .map_or("", |(_, name)| *name),
+                        )
+                        .show_ui(ui, |ui| {
+                            for (value, name) in import::DELIMITERS {
+                                ui.selectable_value(&mut csv.delimiter, value, name);
+                            }
+                        });
+                });
+
+                if csv.delimiter != delimiter {
+                    let name = wizard.sheets[0].name.to_owned();
+                    if let Ok(sheet) = import::read_csv(&name, &csv.content, csv.delimiter) {
+                        wizard.mapping = ColumnMapping::guess(&sheet.headers);
+                        wizard.sheets = vec![sheet];
+                    }
+                }
+
+                ui.separator();
+            }
+
+            ui.label(egui::RichText::new("Sheets").text_style(heading3()));
+            for (sheet, selected) in wizard.sheets.iter().zip(wizard.selected.iter_mut()) {
+                ui.checkbox(
+                    selected,
+                    format!("{} ({} rows)", sheet.name, sheet.rows.len()),
+                );
+            }
+            ui.checkbox(&mut wizard.sheet_as_category, "Use sheet name as category");
+
+            ui.separator();
+            ui.label(egui::RichText::new("Columns").text_style(heading3()));
+
+            let headers = wizard.headers().to_vec();
+            egui::Grid::new("import_columns")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (field, column) in
+                        import::FIELDS.iter().zip(wizard.mapping.columns.iter_mut())
+                    {
+                        ui.label(*field);
+                        egui::ComboBox::from_id_source(("import_column", *field))
+                            .selected_text(
+                                column
+                                    .and_then(|idx| headers.get(idx))
+                                    .map_or("(default)", |header| header.as_str()),
+                            )
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(column, None, "(default)");
+                                for (idx, header) in headers.iter().enumerate() {
+                                    ui.selectable_value(column, Some(idx), header);
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                import = ui.button("Import").clicked();
+                ui.label(format!("{} cards", wizard.cards().len()));
+            });
+        });
+
+        if import {
+            let data = wizard.cards();
+
+            if let Some(csv) = &wizard.csv {
+                self.csv_mappings.insert(
+                    csv.key.to_owned(),
+                    CsvMapping::new(csv.delimiter, wizard.mapping.clone(), wizard.headers()),
+                );
+            }
+
+            if wizard.replace {
+                self.card_table.replace(data);
+            } else {
+                self.card_table.extend(data);
+            }
+        }
+
+        if import || !open {
+            self.import_wizard = None;
+        }
+    }
+
+    /// Replaces the database with goals from a `generator.js` style list.
+    fn import_srl(&mut self) -> anyhow::Result<()> {
+        let path = FileDialog::new()
+            .add_filter("SRL Generator", &["js", "json"])
+            .set_directory(&self.save_path)
+            .pick_file();
+
+        if let Some(path) = path {
+            self.card_table
+                .replace(srl::parse(&std::fs::read_to_string(path)?)?);
+        }
+
+        Ok(())
+    }
+
+    fn export_srl(&self) -> anyhow::Result<()> {
+        let path = FileDialog::new()
+            .add_filter("SRL Generator", &["js"])
+            .add_filter("SRL JSON", &["json"])
+            .set_directory(&self.save_path)
+            .save_file();
+
+        if let Some(path) = path {
+            let cards = self.card_table.iter().collect::<Vec<&CardRow>>();
+            let content = if path.extension().is_some_and(|ext| ext == "json") {
+                srl::to_json(&cards)?
+            } else {
+                srl::to_js(&cards)?
+            };
+
+            std::fs::write(path, content)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fills a new board from the card pool, keeping the free space if it is in use.
+    fn new_board(
+        &self,
+        weighted: bool,
+        rng: &mut impl Rng,
+    ) -> anyhow::Result<[String; BOARD_CELLS]> {
+        let mut cells = self.field_size.cells();
+        let free_cell = self.free_cell();
+
+        cells.retain(|idx| Some(*idx) != free_cell);
+
+        let picked = generate(
+            &self.card_pool(),
+            &cells,
+            weighted,
+            &self.board_quotas(),
+            rng,
+        )?;
+        let mut board = core::array::from_fn(|_idx| String::from(""));
+
+        if let Some(idx) = free_cell {
+            board[idx] = self.free_space_text.to_owned();
+        }
+        for (idx, text) in picked {
+            board[idx] = text;
+        }
+
+        Ok(board)
+    }
+
+    fn randomize(&mut self, weighted: bool) -> anyhow::Result<()> {
+        self.board = self.new_board(weighted, &mut thread_rng())?;
+
+        Ok(())
+    }
+
+    /// Boards are generated from the seed, so the same settings print the same cards.
+    fn export_pdf(&self) -> anyhow::Result<()> {
+        let boards = if self.pdf_options.current_board {
+            vec![self.board.clone()]
+        } else {
+            let mut rng = StdRng::seed_from_u64(self.pdf_options.seed);
+            (0..self.pdf_options.boards)
+                .map(|_| self.new_board(self.pdf_options.weighted, &mut rng))
+                .collect::<anyhow::Result<Vec<_>>>()?
+        };
+
+        let Some(path) = FileDialog::new()
+            .add_filter("PDF", &["pdf"])
+            .set_directory(&self.save_path)
+            .save_file()
+        else {
+            return Ok(());
+        };
+
+        pdf::render_pdf(
+            &boards,
+            self.field_size,
+            &self.pdf_options,
+            &self.export_options.title,
+            &path,
+        )
+    }
+
+    fn show_pdf_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_pdf;
+        let mut export = false;
+
+        egui::Window::new("PDF Export")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let options = &mut self.pdf_options;
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut options.current_board, true, "Current Board");
+                    ui.radio_value(&mut options.current_board, false, "Generate");
+                });
+
+                egui::Grid::new("pdf_options")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Title");
+                        ui.text_edit_singleline(&mut self.export_options.title);
+                        ui.end_row();
+
+                        ui.add_enabled_ui(!options.current_board, |ui| {
+                            ui.label("Boards");
+                        });
+                        ui.add_enabled(
+                            !options.current_board,
+                            egui::DragValue::new(&mut options.boards).clamp_range(1..=500),
+                        );
+                        ui.end_row();
+
+                        ui.add_enabled_ui(!options.current_board, |ui| {
+                            ui.label("Seed");
+                        });
+                        ui.add_enabled_ui(!options.current_board, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut options.seed));
+                                if ui.button("Random").clicked() {
+                                    options.seed = thread_rng().gen_range(0..1_000_000);
                                 }
+                                ui.checkbox(&mut options.weighted, "Weighted");
+                            });
+                        });
+                        ui.end_row();
 
-                                let items_iter = self.card_table.iter();
-                                let mut rng = thread_rng();
-                                let mut samples: Option<Vec<&CardRow>> = None;
-
-                                if self.category_select.ne("All") {
-                                    samples = items_iter
-                                        .filter(|i| {
-                                            i.0.to_owned().eq(&self.category_select.to_owned())
-                                                && i.3
-                                        })
-                                        .choose_multiple(
-                                            &mut rng,
-                                            self.field_size as usize * self.field_size as usize,
-                                        )
-                                        .into();
-                                } else {
-                                    samples = items_iter
-                                        .filter(|i| i.3)
-                                        .choose_multiple(
-                                            &mut rng,
-                                            self.field_size as usize * self.field_size as usize,
-                                        )
-                                        .into();
+                        ui.label("Per Page");
+                        egui::ComboBox::from_id_source("pdf_per_page")
+                            .selected_text(options.per_page.to_string())
+                            .show_ui(ui, |ui| {
+                                for count in PER_PAGE {
+                                    ui.selectable_value(
+                                        &mut options.per_page,
+                                        count,
+                                        count.to_string(),
+                                    );
                                 }
+                            });
+                        ui.end_row();
+
+                        ui.label("Font Size");
+                        ui.add(
+                            egui::DragValue::new(&mut options.font_size)
+                                .clamp_range(6.0..=48.0)
+                                .suffix(" pt"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Cell Size");
+                        ui.add(
+                            egui::DragValue::new(&mut options.cell_size)
+                                .clamp_range(30.0..=200.0)
+                                .suffix(" pt"),
+                        );
+                        ui.end_row();
+                    });
 
-                                let mut result: Vec<&CardRow> = samples.unwrap();
-
-                                if result.len()
-                                    >= (self.field_size as usize * self.field_size as usize)
-                                {
-                                    result.shuffle(&mut rng);
-
-                                    match self.field_size {
-                                        FieldSize::Three => {
-                                            let mut consume = result.iter();
-                                            for c in 1..=3 {
-                                                for r in 1..=3 {
-                                                    *self.board[c * 5 + r] =
-                                                        consume.next().unwrap().1.to_owned();
-                                                }
-                                            }
+                export = ui.button("Export").clicked();
+            });
+
+        self.show_pdf = open;
+
+        if export {
+            if let Err(err) = self.export_pdf() {
+                self.toasts.dismiss_all_toasts();
+                toast_cb(self.toasts.error(err.to_string()));
+            }
+        }
+    }
+}
+
+impl eframe::App for BingoSyncGen {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.store_link();
+
+        #[cfg(feature = "sqlite")]
+        if self.store.is_some() {
+            match self.sync_store() {
+                Ok(()) => {
+                    // Cards are in the store, only names and settings go to the app state.
+                    let cards = self
+                        .databases
+                        .iter_mut()
+                        .map(|database| std::mem::take(&mut database.cards))
+                        .collect::<Vec<_>>();
+
+                    eframe::set_value(storage, eframe::APP_KEY, self);
+
+                    for (database, cards) in self.databases.iter_mut().zip(cards) {
+                        database.cards = cards;
+                    }
+                    return;
+                }
+                Err(err) => log::error!("Failed to save the card store: {}", err),
+            }
+        }
+
+        self.store_database();
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.generated = serde_json::to_string_pretty(&self.bingo_cards()).unwrap();
+        self.update_title(ctx);
+        self.check_link(ctx);
+
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("New Project").clicked() {
+                        self.request_project(ProjectAction::New);
+                        ui.close_menu();
+                    }
+                    if ui.button("Open Project...").clicked() {
+                        match self.pick_project() {
+                            Ok(Some((path, project))) => {
+                                self.request_project(ProjectAction::Open(path, Box::new(project)))
+                            }
+                            Ok(None) => {}
+                            Err(err) => {
+                                self.toasts.dismiss_all_toasts();
+                                toast_cb(self.toasts.error(err.to_string()));
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Save Project").clicked() {
+                        if let Err(err) = self.save_project(false) {
+                            self.toasts.dismiss_all_toasts();
+                            toast_cb(self.toasts.error(err.to_string()));
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Save Project As...").clicked() {
+                        if let Err(err) = self.save_project(true) {
+                            self.toasts.dismiss_all_toasts();
+                            toast_cb(self.toasts.error(err.to_string()));
+                        }
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if self.selected_panel == MainPanel::Board && ui.button("Clear Board").clicked()
+                    {
+                        for i in 0..self.board.len() {
+                            self.board[i] = "".to_owned();
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Quit").clicked() {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+
+                ui.add_space(16.0);
+
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.selected_panel, MainPanel::Board, "Bingo Board");
+                    ui.selectable_value(&mut self.selected_panel, MainPanel::Database, "Database");
+                    ui.selectable_value(
+                        &mut self.selected_panel,
+                        MainPanel::Statistics,
+                        "Statistics",
+                    );
+                });
+
+                if self.selected_panel == MainPanel::Board {
+                    ui.add_space(16.0);
+                    ui.toggle_value(&mut self.show_validation, "Validation");
+
+                    let mut playing = self.playtest.is_some();
+                    if ui.toggle_value(&mut playing, "Play").changed() {
+                        self.playtest = playing.then(Playtest::default);
+                    }
+                }
+            })
+        });
+
+        let report = (self.show_validation && self.selected_panel == MainPanel::Board).then(|| {
+            BoardReport::new(
+                &self.board,
+                self.field_size,
+                self.free_cell(),
+                self.card_table.iter(),
+            )
+        });
+
+        if let Some(report) = &report {
+            egui::SidePanel::right("validation_panel")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        show_report(ui, report);
+                    });
+                });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            match self.selected_panel {
+                MainPanel::Board => {
+                    if self.playtest.is_some() {
+                        self.show_playtest(ui);
+                    } else {
+                        egui::Grid::new("bingo_grid")
+                            .num_columns(5)
+                            .spacing([16.0, 16.0])
+                            .show(ui, |ui| {
+                                for c in 0..5 {
+                                    for r in 0..5 {
+                                        let idx = c * 5 + r;
+                                        let response = ui.add_sized(
+                                            Vec2::new(128.0, 128.0),
+                                            egui::TextEdit::multiline(&mut self.board[idx])
+                                                .font(TextStyle::Monospace),
+                                        );
+
+                                        if report
+                                            .as_ref()
+                                            .is_some_and(|report| report.warnings.contains(&idx))
+                                        {
+                                            ui.painter().rect_stroke(
+                                                response.rect,
+                                                2.0,
+                                                egui::Stroke::new(2.0, ui.visuals().error_fg_color),
+                                            );
                                         }
-                                        FieldSize::Four => {
-                                            let mut consume = result.iter();
-                                            for c in 0..=3 {
-                                                for r in 0..=3 {
-                                                    *self.board[c * 5 + r] =
-                                                        consume.next().unwrap().1.to_owned();
-                                                }
-                                            }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+
+                        ui.separator();
+
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Generated");
+                                if ui.button("Save").clicked() {
+                                    let save_path = FileDialog::new()
+                                        .add_filter("JSON", &["json"])
+                                        .add_filter("Text File", &["txt"])
+                                        .add_filter("PNG", &["png"])
+                                        .add_filter("HTML", &["html", "htm"])
+                                        .add_filter("Markdown", &["md"])
+                                        .set_directory(&self.save_path)
+                                        .save_file();
+
+                                    if let Some(path) = save_path {
+                                        if let Err(err) = self.save_board(&path) {
+                                            self.toasts.dismiss_all_toasts();
+                                            toast_cb(self.toasts.error(err.to_string()));
                                         }
-                                        FieldSize::Five => {
-                                            for (idx, s) in result.iter().enumerate() {
-                                                *self.board[idx] = s.1.to_owned();
+                                    }
+                                }
+
+                                ui.menu_button("Copy", |ui| {
+                                    for format in CopyFormat::ALL {
+                                        if ui.button(format.label()).clicked() {
+                                            ui.close_menu();
+                                            if let Err(err) = self.copy_board(format) {
+                                                self.toasts.dismiss_all_toasts();
+                                                toast_cb(self.toasts.error(err.to_string()));
                                             }
                                         }
                                     }
-                                } else {
-                                    self.toasts.dismiss_all_toasts();
-                                    toast_cb(&mut self.toasts.error("Not Enough Samples"));
-                                }
-                            }
-                            if ui.button("W. Randomize").clicked() {
-                                for i in 0..self.board.len() {
-                                    *self.board[i] = "".to_owned();
-                                }
+                                });
 
-                                let items_iter = self.card_table.iter();
-                                let mut rng = thread_rng();
-                                let mut samples: Option<Vec<&CardRow>> = None;
-
-                                if self.category_select.ne("All") {
-                                    samples = items_iter
-                                        .filter(|i| {
-                                            i.0.to_owned().eq(&self.category_select.to_owned())
-                                                && i.3
-                                        })
-                                        .collect::<Vec<&CardRow>>()
-                                        .into();
-                                } else {
-                                    samples = items_iter
-                                        .filter(|i| i.3)
-                                        .collect::<Vec<&CardRow>>()
-                                        .into();
+                                if ui.button("Paste").clicked() {
+                                    if let Err(err) = self.paste_board() {
+                                        self.toasts.dismiss_all_toasts();
+                                        toast_cb(self.toasts.error(err.to_string()));
+                                    }
                                 }
 
-                                let result = samples.unwrap();
-
-                                if result.len()
-                                    >= (self.field_size as usize * self.field_size as usize)
-                                {
-                                    let builder = WalkerTableBuilder::new(
-                                        &result
-                                            .clone()
-                                            .iter()
-                                            .map(|item| item.2 as f32 / 100.0)
-                                            .collect::<Vec<f32>>(),
-                                    );
-                                    let wa_table = builder.build();
-                                    let mut visited: Vec<usize> = vec![];
-
-                                    match self.field_size {
-                                        FieldSize::Three => {
-                                            for c in 1..=3 {
-                                                for r in 1..=3 {
-                                                    while let idx = wa_table.next_rng(&mut rng) {
-                                                        if visited.contains(&idx) {
-                                                            continue;
-                                                        }
-
-                                                        visited.push(idx);
-
-                                                        *self.board[c * 5 + r] =
-                                                            result[idx].1.to_owned();
-
-                                                        break;
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        FieldSize::Four => {
-                                            for c in 0..=3 {
-                                                for r in 0..=3 {
-                                                    while let idx = wa_table.next_rng(&mut rng) {
-                                                        if visited.contains(&idx) {
-                                                            continue;
-                                                        }
-
-                                                        visited.push(idx);
-
-                                                        *self.board[c * 5 + r] =
-                                                            result[idx].1.to_owned();
-
-                                                        break;
-                                                    }
-                                                }
-                                            }
+                                ui.label("Database");
+                                self.database_select(ui, "board_database_select");
+
+                                ui.label("Category".to_owned());
+
+                                egui::ComboBox::from_id_source("category_select")
+                                    .selected_text(self.category_select.to_owned())
+                                    .show_ui(ui, |ui| {
+                                        for item in [
+                                            vec![String::from("All")],
+                                            self.card_table
+                                                .iter()
+                                                .map(|item| item.0.to_owned())
+                                                .collect::<Vec<String>>()
+                                                .iter()
+                                                .unique()
+                                                .map(|item| item.to_owned())
+                                                .collect::<Vec<String>>(),
+                                        ]
+                                        .concat()
+                                        {
+                                            ui.selectable_value(
+                                                &mut self.category_select,
+                                                item.to_owned(),
+                                                item.to_owned(),
+                                            );
                                         }
-                                        FieldSize::Five => {
-                                            for e_idx in (0..25) {
-                                                while let idx = wa_table.next_rng(&mut rng) {
-                                                    if visited.contains(&idx) {
-                                                        continue;
-                                                    }
+                                    });
+
+                                egui::ComboBox::from_id_source("field_select")
+                                    .selected_text(match self.field_size {
+                                        FieldSize::Three => String::from("3x3"),
+                                        FieldSize::Four => String::from("4x4"),
+                                        FieldSize::Five => String::from("5x5"),
+                                    })
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(
+                                            &mut self.field_size,
+                                            FieldSize::Three,
+                                            "3x3",
+                                        );
+                                        ui.selectable_value(
+                                            &mut self.field_size,
+                                            FieldSize::Four,
+                                            "4x4",
+                                        );
+                                        ui.selectable_value(
+                                            &mut self.field_size,
+                                            FieldSize::Five,
+                                            "5x5",
+                                        );
+                                    });
+
+                                if ui.button("Randomize").clicked() {
+                                    if let Err(err) = self.randomize(false) {
+                                        self.toasts.dismiss_all_toasts();
+                                        toast_cb(self.toasts.error(err.to_string()));
+                                    }
+                                }
+                                if ui.button("W. Randomize").clicked() {
+                                    if let Err(err) = self.randomize(true) {
+                                        self.toasts.dismiss_all_toasts();
+                                        toast_cb(self.toasts.error(err.to_string()));
+                                    }
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.add_enabled_ui(self.field_size.has_center(), |ui| {
+                                    ui.checkbox(&mut self.free_space, "Free Space");
+                                    ui.add_enabled(
+                                        self.free_space,
+                                        egui::TextEdit::singleline(&mut self.free_space_text),
+                                    );
+                                });
 
-                                                    visited.push(idx);
+                                ui.menu_button("Quotas", |ui| self.show_quotas(ui));
 
-                                                    *self.board[e_idx] = result[idx].1.to_owned();
+                                ui.menu_button("Boards", |ui| self.show_saved_boards(ui));
 
-                                                    break;
-                                                }
-                                            }
+                                ui.menu_button("Transform", |ui| {
+                                    for transform in Transform::ALL {
+                                        if ui.button(transform.label()).clicked() {
+                                            transform.apply(
+                                                &mut self.board,
+                                                self.field_size,
+                                                self.free_space,
+                                                &mut thread_rng(),
+                                            );
+                                            ui.close_menu();
                                         }
                                     }
-                                } else {
-                                    self.toasts.dismiss_all_toasts();
-                                    toast_cb(&mut self.toasts.error("Not Enough Samples"));
+                                });
+
+                                ui.menu_button("Export Options", |ui| {
+                                    egui::Grid::new("export_options").num_columns(2).show(
+                                        ui,
+                                        |ui| {
+                                            ui.label("Title");
+                                            ui.text_edit_singleline(&mut self.export_options.title);
+                                            ui.end_row();
+
+                                            ui.label("Footer");
+                                            ui.text_edit_singleline(
+                                                &mut self.export_options.footer,
+                                            );
+                                            ui.end_row();
+                                        },
+                                    );
+                                    ui.checkbox(&mut self.export_options.dark, "Dark Theme");
+                                    ui.checkbox(
+                                        &mut self.export_options.clickable,
+                                        "Click to Mark (HTML)",
+                                    );
+                                });
+
+                                if ui.button("Analyze").clicked() {
+                                    self.show_analysis = true;
                                 }
-                            }
-                        });
 
-                        egui::ScrollArea::vertical().show(ui, |ui| {
-                            ui.add_sized(
-                                ui.available_size(),
-                                egui::TextEdit::multiline(&mut self.generated)
-                                    .font(TextStyle::Monospace),
-                            );
+                                if ui.button("Print PDF").clicked() {
+                                    self.show_pdf = true;
+                                }
+
+                                #[cfg(feature = "bingosync")]
+                                if ui.button("Create Room").clicked() {
+                                    self.room.open = true;
+                                }
+
+                                #[cfg(feature = "bingosync")]
+                                if ui.button("Watch Room").clicked() {
+                                    self.watch.open = true;
+                                }
+                            });
+
+                            egui::ScrollArea::vertical().show(ui, |ui| {
+                                ui.add_sized(
+                                    ui.available_size(),
+                                    egui::TextEdit::multiline(&mut self.generated)
+                                        .font(TextStyle::Monospace),
+                                );
+                            });
                         });
-                    });
+                    }
                 }
                 MainPanel::Database => {
+                    ui.horizontal(|ui| {
+                        ui.label("Database");
+                        self.database_select(ui, "database_select");
+                        ui.label("Name");
+                        if ui
+                            .text_edit_singleline(&mut self.database_name)
+                            .lost_focus()
+                        {
+                            if let Err(err) = self.rename_database() {
+                                self.toasts.dismiss_all_toasts();
+                                toast_cb(self.toasts.error(err.to_string()));
+                            }
+                        }
+                        if ui.button("New").clicked() {
+                            self.new_database();
+                        }
+                        if ui
+                            .add_enabled(self.databases.len() > 1, egui::Button::new("Delete"))
+                            .clicked()
+                        {
+                            self.delete_database = true;
+                        }
+
+                        ui.separator();
+
+                        match &self.link {
+                            Some(link) => {
+                                ui.label(format!(
+                                    "Linked to {}",
+                                    link.path
+                                        .file_name()
+                                        .map(|name| name.to_string_lossy())
+                                        .unwrap_or_default()
+                                ))
+                                .on_hover_text(link.path.display().to_string());
+                                if ui.button("Unlink").clicked() {
+                                    self.link = None;
+                                    self.link_conflict = None;
+                                }
+                            }
+                            None => {
+                                if ui.button("Link File").clicked() {
+                                    if let Err(err) = self.link_file() {
+                                        self.toasts.dismiss_all_toasts();
+                                        toast_cb(self.toasts.error(err.to_string()));
+                                    }
+                                }
+                            }
+                        }
+                    });
+
                     ui.horizontal(|ui| {
                         ui.label("Search");
-                        ui.text_edit_singleline(&mut self.card_viewer.filter);
+                        ui.text_edit_singleline(&mut self.card_viewer.search.filter)
+                            .on_hover_text(search::SYNTAX_HELP);
+                        ui.toggle_value(&mut self.card_viewer.search.case_sensitive, "Aa")
+                            .on_hover_text("Match case");
+                        ui.toggle_value(&mut self.card_viewer.search.regex, ".*")
+                            .on_hover_text("Regex");
+                        egui::ComboBox::from_id_source("enabled_filter")
+                            .selected_text(match self.card_viewer.search.enabled {
+                                None => "All",
+                                Some(true) => "Enabled",
+                                Some(false) => "Disabled",
+                            })
+                            .show_ui(ui, |ui| {
+                                let enabled = &mut self.card_viewer.search.enabled;
+                                ui.selectable_value(enabled, None, "All");
+                                ui.selectable_value(enabled, Some(true), "Enabled");
+                                ui.selectable_value(enabled, Some(false), "Disabled");
+                            });
+                        if let Some(err) = &self.card_viewer.search_error {
+                            ui.colored_label(egui::Color32::RED, "⚠").on_hover_text(err);
+                        }
                         if ui.button("Add Row").clicked() {
                             self.card_table.extend([self.card_viewer.new_empty_row()]);
                         }
+                        ui.menu_button("Bulk Edit", |ui| self.show_bulk_menu(ui));
                         if ui.button("Import Add").clicked() {
-                            let save_path = FileDialog::new()
-                                .add_filter("CSV", &["csv"])
-                                .set_directory(&self.save_path)
-                                .pick_file();
-
-                            if let Some(path) = save_path {
-                                let file = File::open(path).unwrap();
-                                let reader = BufReader::new(file);
-                                let mut dataset = csv::Reader::from_reader(reader);
-
-                                let mut data: Vec<CardRow> = Vec::new();
-
-                                for result in dataset.deserialize() {
-                                    let record: CardRow = result.unwrap();
-                                    data.push(record);
-                                }
-
-                                self.card_table.extend(data);
+                            if let Err(err) = self.import_database(false) {
+                                self.toasts.dismiss_all_toasts();
+                                toast_cb(self.toasts.error(err.to_string()));
                             }
                         }
                         if ui.button("Import").clicked() {
-                            let save_path = FileDialog::new()
-                                .add_filter("CSV", &["csv"])
-                                .set_directory(&self.save_path)
-                                .pick_file();
-
-                            if let Some(path) = save_path {
-                                let file = File::open(path).unwrap();
-                                let reader = BufReader::new(file);
-                                let mut dataset = csv::Reader::from_reader(reader);
-
-                                let mut data: Vec<CardRow> = Vec::new();
-
-                                for result in dataset.deserialize() {
-                                    let record: CardRow = result.unwrap();
-                                    data.push(record);
-                                }
-
-                                self.card_table.replace(data);
+                            if let Err(err) = self.import_database(true) {
+                                self.toasts.dismiss_all_toasts();
+                                toast_cb(self.toasts.error(err.to_string()));
+                            }
+                        }
+                        if ui.button("Compare").clicked() {
+                            if let Err(err) = self.open_compare() {
+                                self.toasts.dismiss_all_toasts();
+                                toast_cb(self.toasts.error(err.to_string()));
                             }
                         }
                         if ui.button("Export").clicked() {
                             let save_path = FileDialog::new()
                                 .add_filter("CSV", &["csv"])
+                                .add_filter("JSON", &["json"])
+                                .add_filter("TOML", &["toml"])
                                 .set_directory(&self.save_path)
                                 .save_file();
 
                             if let Some(path) = save_path {
-                                let mut writer = csv::Writer::from_path(path).unwrap();
-
-                                writer
-                                    .write_record(&["category", "text", "weight", "enabled"])
-                                    .unwrap(); // Header
-
-                                for record in self.card_table.iter() {
-                                    writer.serialize(record).unwrap();
+                                if let Err(err) = database::save(&path, self.card_table.iter()) {
+                                    self.toasts.dismiss_all_toasts();
+                                    toast_cb(self.toasts.error(err.to_string()));
                                 }
                             }
                         }
+                        ui.menu_button("SRL", |ui| {
+                            if ui.button("Import").clicked() {
+                                ui.close_menu();
+                                if let Err(err) = self.import_srl() {
+                                    self.toasts.dismiss_all_toasts();
+                                    toast_cb(self.toasts.error(err.to_string()));
+                                }
+                            }
+                            if ui.button("Export").clicked() {
+                                ui.close_menu();
+                                if let Err(err) = self.export_srl() {
+                                    self.toasts.dismiss_all_toasts();
+                                    toast_cb(self.toasts.error(err.to_string()));
+                                }
+                            }
+                        });
                     });
 
+                    self.card_viewer.enabled_count = (
+                        self.card_table.iter().filter(|card| card.3).count(),
+                        self.card_table.len(),
+                    );
+                    self.card_viewer.pool = (
+                        self.category_select.to_owned(),
+                        self.card_pool().iter().map(|card| card.2.max(0.0)).sum(),
+                    );
                     ui.add(egui_data_table::Renderer::new(
                         &mut self.card_table,
                         &mut self.card_viewer,
                     ));
                 }
+                MainPanel::Statistics => self.show_statistics(ui),
             }
 
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
@@ -664,6 +3174,283 @@ impl eframe::App for BingoSyncGen {
             });
         });
 
+        self.show_import_wizard(ctx);
+
+        self.show_pdf_window(ctx);
+
+        self.show_link_conflict(ctx);
+
+        self.show_compare(ctx);
+
+        self.show_project_confirm(ctx);
+        self.show_bulk_confirm(ctx);
+
+        if self.delete_database {
+            let mut confirmed = false;
+
+            egui::Window::new("Delete Database")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Delete \"{}\" with {} cards?",
+                        self.databases[self.active_database].name,
+                        self.card_table.len()
+                    ));
+                    ui.horizontal(|ui| {
+                        confirmed = ui.button("Delete").clicked();
+                        if ui.button("Cancel").clicked() {
+                            self.delete_database = false;
+                        }
+                    });
+                });
+
+            if confirmed {
+                self.remove_database();
+                self.delete_database = false;
+            }
+        }
+
+        if let Some(task) = &self.analysis_task {
+            match task.try_recv() {
+                Ok(analysis) => {
+                    self.analysis = Some(analysis);
+                    self.analysis_task = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(Duration::from_millis(100));
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.analysis_task = None,
+            }
+        }
+
+        let mut show_analysis = self.show_analysis;
+        let mut run_analysis = false;
+        let mut cancel_analysis = false;
+        let mut export_analysis = false;
+
+        egui::Window::new("Analysis")
+            .open(&mut show_analysis)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Runs");
+                    ui.add(
+                        egui::DragValue::new(&mut self.analysis_runs)
+                            .clamp_range(1..=100_000)
+                            .speed(100.0),
+                    );
+                    ui.checkbox(&mut self.analysis_weighted, "Weighted");
+                    match &self.analysis_task {
+                        Some(task) => {
+                            cancel_analysis = ui.button("Cancel").clicked();
+                            ui.add(egui::ProgressBar::new(task.progress()).show_percentage());
+                        }
+                        None => {
+                            run_analysis = ui.button("Run").clicked();
+                            export_analysis = ui
+                                .add_enabled(self.analysis.is_some(), egui::Button::new("Export"))
+                                .clicked();
+                        }
+                    }
+                });
+
+                if let Some(analysis) = &self.analysis {
+                    ui.separator();
+                    show_analysis_result(ui, analysis);
+                }
+            });
+
+        self.show_analysis = show_analysis;
+
+        // Closing the window stops the analysis too.
+        if cancel_analysis || !show_analysis {
+            self.analysis_task = None;
+        }
+
+        if run_analysis {
+            let ctx = ctx.clone();
+
+            self.analysis_task = Some(AnalysisTask::start(
+                self.card_pool().into_iter().cloned().collect(),
+                AnalysisOptions {
+                    size: self.field_size,
+                    free_cell: self.free_cell(),
+                    weighted: self.analysis_weighted,
+                    quotas: self.board_quotas(),
+                    runs: self.analysis_runs,
+                },
+                move || ctx.request_repaint(),
+            ));
+        }
+
+        if export_analysis {
+            let save_path = FileDialog::new()
+                .add_filter("CSV", &["csv"])
+                .set_directory(&self.save_path)
+                .save_file();
+
+            if let (Some(path), Some(analysis)) = (save_path, &self.analysis) {
+                if let Err(err) = analysis.export(&path) {
+                    self.toasts.dismiss_all_toasts();
+                    toast_cb(self.toasts.error(err.to_string()));
+                }
+            }
+        }
+
+        #[cfg(feature = "bingosync")]
+        self.show_room_window(ctx);
+
+        #[cfg(feature = "bingosync")]
+        self.show_watch_window(ctx);
+
         self.toasts.show(ctx);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_cards_move_into_a_database() {
+        let cards = vec![
+            CardRow("Combat".into(), "Win a fight".into(), 2.0, true),
+            CardRow("Items".into(), "Find a key".into(), 1.0, false),
+        ];
+
+        // State saved before named databases existed.
+        let mut app: BingoSyncGen = ron::from_str(
+            r#"(card_table_data: [("Combat", "Win a fight", 2.0, true), ("Items", "Find a key", 1.0, false)])"#,
+        )
+        .unwrap();
+        app.migrate_cards();
+
+        assert!(app.card_table_data.is_empty());
+        assert_eq!(app.databases.len(), 1);
+        assert_eq!(app.databases[0].name, "Default");
+        assert_eq!(app.databases[0].cards, cards);
+
+        let mut saved: BingoSyncGen = ron::from_str(&ron::to_string(&app).unwrap()).unwrap();
+        saved.migrate_cards();
+
+        assert_eq!(saved.databases.len(), 1);
+        assert_eq!(saved.databases[0].cards, cards);
+    }
+
+    #[test]
+    fn fresh_state_gets_an_empty_database() {
+        let mut app: BingoSyncGen = ron::from_str("()").unwrap();
+        app.migrate_cards();
+
+        assert_eq!(app.databases.len(), 1);
+        assert!(app.databases[0].cards.is_empty());
+    }
+
+    #[test]
+    fn database_names_stay_unique() {
+        let mut app: BingoSyncGen = ron::from_str(
+            r#"(databases: [(name: "Games"), (name: " "), (name: "Games"), (name: "Games (2)")])"#,
+        )
+        .unwrap();
+        app.unique_database_names();
+
+        let names = |app: &BingoSyncGen| {
+            app.databases
+                .iter()
+                .map(|database| database.name.to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&app), ["Games", "Database", "Games (3)", "Games (2)"]);
+
+        app.load_database(1);
+        for taken in ["", "  ", "Games", "Games (2)"] {
+            app.database_name = taken.into();
+            assert!(app.rename_database().is_err(), "{:?}", taken);
+            assert_eq!(app.database_name, "Database");
+        }
+
+        app.database_name = " Races ".into();
+        app.rename_database().unwrap();
+        assert_eq!(names(&app)[1], "Races");
+        assert_eq!(app.database_name, "Races");
+    }
+
+    #[test]
+    fn pasted_lists_set_the_field_size() {
+        let mut app: BingoSyncGen = ron::from_str("()").unwrap();
+        app.field_size = FieldSize::Three;
+
+        let goals = (1..=25).map(|i| format!("Goal {}", i)).collect::<Vec<_>>();
+        app.paste_text(&goals.join("\n")).unwrap();
+
+        assert_eq!(app.field_size, FieldSize::Five);
+        assert_eq!(app.board.to_vec(), goals);
+
+        // JSON always has all 25 cells, so it keeps the field.
+        app.field_size = FieldSize::Three;
+        app.paste_text(&CopyFormat::Json.format(&app.board, FieldSize::Five))
+            .unwrap();
+
+        assert_eq!(app.field_size, FieldSize::Three);
+        assert_eq!(app.board.to_vec(), goals);
+    }
+
+    #[test]
+    fn linked_edits_survive_a_switch() {
+        let path = std::env::temp_dir().join(format!("linked-{}.json", std::process::id()));
+        let file_cards = vec![CardRow("Combat".into(), "Win a fight".into(), 1.0, true)];
+        let local_cards = vec![CardRow("Combat".into(), "Win two fights".into(), 1.0, true)];
+        database::save(&path, file_cards.iter()).unwrap();
+
+        let mut app: BingoSyncGen = ron::from_str("()").unwrap();
+        app.migrate_cards();
+        app.load_database(0);
+
+        let mut link = FileLink::new(path.clone(), &file_cards);
+        app.card_table.replace(link.poll().unwrap().unwrap());
+        app.link = Some(link);
+        app.card_table.replace(local_cards.clone());
+
+        let ctx = egui::Context::default();
+        let check = |app: &mut BingoSyncGen| {
+            app.link_checked = -1.0;
+            app.check_link(&ctx);
+        };
+
+        // Switching away and back keeps the edits while the file is unchanged.
+        app.new_database();
+        app.switch_database(0);
+        check(&mut app);
+        assert!(app.link_conflict.is_none());
+        assert!(app.card_table.iter().eq(local_cards.iter()));
+
+        // So does a restart, the link is in the app state but not in the database settings.
+        app.store_database();
+        let state = ron::to_string(&app).unwrap();
+        assert!(!ron::to_string(&app.project()).unwrap().contains("linked-"));
+        let mut app: BingoSyncGen = ron::from_str(&state).unwrap();
+        app.load_database(0);
+        check(&mut app);
+        assert!(app.link.is_some());
+        assert!(app.link_conflict.is_none());
+        assert!(app.card_table.iter().eq(local_cards.iter()));
+
+        // A changed file asks instead of replacing them.
+        let changed_cards = vec![CardRow("Items".into(), "Find a key".into(), 1.0, true)];
+        database::save(&path, changed_cards.iter()).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        app.new_database();
+        app.switch_database(0);
+        check(&mut app);
+        assert_eq!(app.link_conflict, Some(changed_cards));
+        assert!(app.card_table.iter().eq(local_cards.iter()));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
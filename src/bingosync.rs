@@ -0,0 +1,777 @@
+//! Minimal client for the BingoSync site, it talks to the same form the site uses.
+
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::board::BOARD_CELLS;
+
+pub(crate) const DEFAULT_URL: &str = "https://bingosync.com";
+
+/// How often the room feed is polled while watching.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Failed feed polls in a row after which watching stops.
+const MAX_POLL_FAILURES: u32 = 5;
+
+/// Longest wait between polls after failures.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// "Custom (Advanced)" game with the "Fixed Board" variant, which takes the board as is.
+const CUSTOM_GAME_TYPE: &str = "18";
+const CUSTOM_VARIANT_TYPE: &str = "18";
+
+#[derive(Clone)]
+pub(crate) struct RoomSettings {
+    pub(crate) name: String,
+    pub(crate) passphrase: String,
+    pub(crate) nickname: String,
+    pub(crate) lockout: bool,
+    pub(crate) hide_card: bool,
+    pub(crate) spectator: bool,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        Self {
+            name: String::from(""),
+            passphrase: String::from(""),
+            nickname: String::from("bingosync-gen"),
+            lockout: false,
+            hide_card: true,
+            spectator: true,
+        }
+    }
+}
+
+pub(crate) struct RoomClient {
+    agent: ureq::Agent,
+    base_url: String,
+}
+
+impl RoomClient {
+    /// `base_url` is the site root, a local server can be used in place of BingoSync.
+    pub(crate) fn new(base_url: &str) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().redirects(0).build(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// Creates a room with `custom_json` as its board and returns the room URL.
+    pub(crate) fn create_room(
+        &self,
+        settings: &RoomSettings,
+        custom_json: &str,
+    ) -> anyhow::Result<String> {
+        if settings.name.trim().is_empty() {
+            bail!("Room name is required");
+        }
+        if settings.passphrase.is_empty() {
+            bail!("Room password is required");
+        }
+
+        let index_url = format!("{}/", self.base_url);
+
+        // The form is protected by a CSRF token, the matching cookie is kept by the agent.
+        let page = self
+            .agent
+            .get(&index_url)
+            .call()
+            .context("Failed to open BingoSync")?
+            .into_string()?;
+        let token = csrf_token(&page).ok_or_else(|| anyhow!("No CSRF token on the page"))?;
+
+        let lockout_mode = if settings.lockout { "2" } else { "1" };
+        let mut form = vec![
+            ("csrfmiddlewaretoken", token.as_str()),
+            ("room_name", settings.name.as_str()),
+            ("passphrase", settings.passphrase.as_str()),
+            ("nickname", settings.nickname.as_str()),
+            ("game_type", CUSTOM_GAME_TYPE),
+            ("variant_type", CUSTOM_VARIANT_TYPE),
+            ("custom_json", custom_json),
+            ("lockout_mode", lockout_mode),
+            ("seed", ""),
+        ];
+        if settings.spectator {
+            form.push(("is_spectator", "on"));
+        }
+        if settings.hide_card {
+            form.push(("hide_card", "on"));
+        }
+
+        let response = self
+            .agent
+            .post(&index_url)
+            .set("Referer", &index_url)
+            .send_form(&form)
+            .context("Failed to create room")?;
+
+        match response.header("Location") {
+            Some(location) if (300..400).contains(&response.status()) => {
+                Ok(self.absolute_url(location))
+            }
+            // The site renders the form again with errors instead of redirecting.
+            _ => bail!("BingoSync refused to create the room"),
+        }
+    }
+
+    /// Joins the room as a spectator, the session cookie is kept by the agent.
+    pub(crate) fn join_room(
+        &self,
+        room: &str,
+        nickname: &str,
+        passphrase: &str,
+    ) -> anyhow::Result<()> {
+        let body = serde_json::json!({
+            "room": room,
+            "nickname": nickname,
+            "password": passphrase,
+            "is_spectator": true,
+        });
+
+        self.agent
+            .post(&format!("{}/api/join-room", self.base_url))
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .context("Failed to join room")?;
+
+        Ok(())
+    }
+
+    pub(crate) fn board(&self, room: &str) -> anyhow::Result<Vec<Square>> {
+        let body = self
+            .agent
+            .get(&format!("{}/room/{}/board", self.base_url, room))
+            .call()
+            .context("Failed to load room board")?
+            .into_string()?;
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Recent events of the room, oldest first.
+    pub(crate) fn feed(&self, room: &str) -> anyhow::Result<Vec<FeedEvent>> {
+        let body = self
+            .agent
+            .get(&format!("{}/room/{}/feed", self.base_url, room))
+            .call()
+            .context("Failed to load room feed")?
+            .into_string()?;
+
+        Ok(serde_json::from_str::<Feed>(&body)?.events)
+    }
+
+    fn absolute_url(&self, location: &str) -> String {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            location.to_owned()
+        } else {
+            format!("{}/{}", self.base_url, location.trim_start_matches('/'))
+        }
+    }
+}
+
+fn csrf_token(page: &str) -> Option<String> {
+    let start = page.find("name=\"csrfmiddlewaretoken\"")?;
+    let value = &page[start..];
+    let value = &value[value.find("value=\"")? + "value=\"".len()..];
+
+    Some(value[..value.find('"')?].to_owned())
+}
+
+/// Takes the room id out of a room URL, a bare id is returned as is.
+pub(crate) fn room_id(url: &str) -> &str {
+    url.trim_end_matches('/').rsplit('/').next().unwrap_or(url)
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Square {
+    pub(crate) name: String,
+    /// `slot1` to `slot25`, row by row.
+    pub(crate) slot: String,
+    /// Space separated team colors or `blank`.
+    pub(crate) colors: String,
+}
+
+impl Square {
+    /// Board index of the square.
+    pub(crate) fn index(&self) -> Option<usize> {
+        self.slot
+            .trim_start_matches("slot")
+            .parse::<usize>()
+            .ok()
+            .and_then(|slot| slot.checked_sub(1))
+            .filter(|idx| *idx < BOARD_CELLS)
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Player {
+    pub(crate) name: String,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct FeedEvent {
+    #[serde(rename = "type")]
+    pub(crate) kind: String,
+    pub(crate) player: Option<Player>,
+    pub(crate) player_color: Option<String>,
+    pub(crate) square: Option<Square>,
+    #[serde(default)]
+    pub(crate) remove: bool,
+    pub(crate) timestamp: f64,
+}
+
+impl FeedEvent {
+    /// Identifies the event across polls, events of a single timestamp still differ by content.
+    fn key(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.timestamp,
+            self.kind,
+            self.player
+                .as_ref()
+                .map_or("", |player| player.name.as_str()),
+            self.player_color.as_deref().unwrap_or(""),
+            self.square
+                .as_ref()
+                .map_or("", |square| square.slot.as_str()),
+            self.remove
+        )
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Feed {
+    events: Vec<FeedEvent>,
+}
+
+pub(crate) struct WatchSettings {
+    pub(crate) server: String,
+    pub(crate) room: String,
+    pub(crate) passphrase: String,
+    pub(crate) nickname: String,
+    pub(crate) log_path: Option<PathBuf>,
+    pub(crate) poll_interval: Duration,
+}
+
+pub(crate) enum WatchMessage {
+    Board(Vec<Square>),
+    Goal(FeedEvent),
+    Error(String),
+}
+
+/// Background poller of a room, stops when dropped.
+pub(crate) struct RoomWatch {
+    receiver: Receiver<WatchMessage>,
+    stop: Arc<AtomicBool>,
+}
+
+impl RoomWatch {
+    /// Goal events are sent as they show up in the feed and appended to the log file
+    /// as tab separated `timestamp, player, color, action, slot, goal` lines.
+    pub(crate) fn start(settings: WatchSettings, notify: impl Fn() + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        thread::spawn(move || {
+            if let Err(err) = follow_room(&settings, &sender, &notify, &thread_stop) {
+                let _ = sender.send(WatchMessage::Error(err.to_string()));
+                notify();
+            }
+        });
+
+        Self { receiver, stop }
+    }
+
+    pub(crate) fn try_recv(&self) -> Option<WatchMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for RoomWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn follow_room(
+    settings: &WatchSettings,
+    sender: &Sender<WatchMessage>,
+    notify: &impl Fn(),
+    stop: &AtomicBool,
+) -> anyhow::Result<()> {
+    let client = RoomClient::new(&settings.server);
+    let room = room_id(&settings.room);
+
+    client.join_room(room, &settings.nickname, &settings.passphrase)?;
+
+    // Marks made before joining are already on the board, so only newer events count.
+    let mut seen = client
+        .feed(room)?
+        .iter()
+        .map(FeedEvent::key)
+        .collect::<HashSet<_>>();
+
+    if sender
+        .send(WatchMessage::Board(client.board(room)?))
+        .is_err()
+    {
+        return Ok(());
+    }
+    notify();
+
+    let mut log = match &settings.log_path {
+        Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+        None => None,
+    };
+
+    let mut failures = 0;
+
+    while !stop.load(Ordering::Relaxed) {
+        // Every failure in a row doubles the wait, a busy or flaky site gets time to recover.
+        thread::sleep((settings.poll_interval * 2_u32.pow(failures)).min(MAX_BACKOFF));
+
+        let events = match client.feed(room) {
+            Ok(events) => {
+                failures = 0;
+                events
+            }
+            Err(err) if is_auth_error(&err) => return Err(err),
+            Err(err) => {
+                failures += 1;
+                if failures >= MAX_POLL_FAILURES {
+                    return Err(err);
+                }
+                log::warn!("Retrying the room feed: {:#}", err);
+                continue;
+            }
+        };
+
+        for event in events {
+            if event.kind != "goal" || !seen.insert(event.key()) {
+                continue;
+            }
+
+            if let (Some(log), Some(square)) = (&mut log, &event.square) {
+                writeln!(
+                    log,
+                    "{:.3}\t{}\t{}\t{}\t{}\t{}",
+                    event.timestamp,
+                    event
+                        .player
+                        .as_ref()
+                        .map_or("", |player| player.name.as_str()),
+                    event.player_color.as_deref().unwrap_or(""),
+                    if event.remove { "cleared" } else { "marked" },
+                    square.slot,
+                    square.name,
+                )?;
+            }
+
+            if sender.send(WatchMessage::Goal(event)).is_err() {
+                return Ok(());
+            }
+            notify();
+        }
+    }
+
+    Ok(())
+}
+
+/// The room refused the session, polling again won't help.
+fn is_auth_error(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<ureq::Error>(),
+        Some(ureq::Error::Status(401 | 403, _))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+        sync::atomic::AtomicUsize,
+    };
+
+    use super::*;
+
+    struct Request {
+        method: String,
+        path: String,
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    struct Response {
+        status: u16,
+        headers: Vec<(&'static str, String)>,
+        body: String,
+    }
+
+    impl Response {
+        fn ok(body: &str) -> Self {
+            Self {
+                status: 200,
+                headers: vec![],
+                body: body.to_owned(),
+            }
+        }
+    }
+
+    /// Stand-in for the site, every connection gets a single response from `handler`.
+    /// Returns the base URL and the requests as they were served.
+    fn serve(
+        mut handler: impl FnMut(&Request) -> Response + Send + 'static,
+    ) -> (String, Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut request_line = line.split_whitespace();
+                let method = request_line.next().unwrap_or_default().to_owned();
+                let path = request_line.next().unwrap_or_default().to_owned();
+
+                let mut headers = HashMap::new();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(':') {
+                        Some((name, value)) => {
+                            headers.insert(name.to_ascii_lowercase(), value.trim().to_owned());
+                        }
+                        None => break,
+                    }
+                }
+
+                let length = headers
+                    .get("content-length")
+                    .map_or(0, |length| length.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let request = Request {
+                    method,
+                    path,
+                    headers,
+                    body: String::from_utf8(body).unwrap(),
+                };
+                let response = handler(&request);
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                )
+                .unwrap();
+                for (name, value) in response.headers {
+                    write!(stream, "{}: {}\r\n", name, value).unwrap();
+                }
+                write!(stream, "\r\n{}", response.body).unwrap();
+
+                if sender.send(request).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (url, receiver)
+    }
+
+    /// Decodes an `application/x-www-form-urlencoded` body.
+    fn form_fields(body: &str) -> HashMap<String, String> {
+        let decode = |text: &str| {
+            let mut bytes = vec![];
+            let mut chars = text.bytes();
+            while let Some(byte) = chars.next() {
+                match byte {
+                    b'+' => bytes.push(b' '),
+                    b'%' => {
+                        let hex = [chars.next().unwrap(), chars.next().unwrap()];
+                        let hex = std::str::from_utf8(&hex).unwrap();
+                        bytes.push(u8::from_str_radix(hex, 16).unwrap());
+                    }
+                    byte => bytes.push(byte),
+                }
+            }
+            String::from_utf8(bytes).unwrap()
+        };
+
+        body.split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (decode(name), decode(value)))
+            .collect()
+    }
+
+    #[test]
+    fn create_room_posts_the_form() {
+        let (url, requests) = serve(|request| match request.method.as_str() {
+            "GET" => Response {
+                status: 200,
+                headers: vec![("Set-Cookie", String::from("csrftoken=cookie; Path=/"))],
+                body: String::from(
+                    r#"<form><input type="hidden" name="csrfmiddlewaretoken" value="token"></form>"#,
+                ),
+            },
+            _ => Response {
+                status: 302,
+                headers: vec![("Location", String::from("/room/AbC123"))],
+                body: String::new(),
+            },
+        });
+
+        let settings = RoomSettings {
+            name: String::from("Race"),
+            passphrase: String::from("secret"),
+            lockout: true,
+            hide_card: true,
+            ..Default::default()
+        };
+        let custom_json = r#"[{"name": "Goal & more"}]"#;
+
+        let room = RoomClient::new(&format!("{}/", url))
+            .create_room(&settings, custom_json)
+            .unwrap();
+        assert_eq!(room, format!("{}/room/AbC123", url));
+
+        let index = requests.recv().unwrap();
+        assert_eq!((index.method.as_str(), index.path.as_str()), ("GET", "/"));
+
+        let post = requests.recv().unwrap();
+        assert_eq!((post.method.as_str(), post.path.as_str()), ("POST", "/"));
+        assert!(post.headers["cookie"].contains("csrftoken=cookie"));
+
+        let form = form_fields(&post.body);
+        assert_eq!(form["csrfmiddlewaretoken"], "token");
+        assert_eq!(form["room_name"], "Race");
+        assert_eq!(form["passphrase"], "secret");
+        assert_eq!(form["game_type"], CUSTOM_GAME_TYPE);
+        assert_eq!(form["variant_type"], CUSTOM_VARIANT_TYPE);
+        assert_eq!(form["lockout_mode"], "2");
+        assert_eq!(form["hide_card"], "on");
+        assert_eq!(form["custom_json"], custom_json);
+    }
+
+    #[test]
+    fn create_room_without_redirect_fails() {
+        let (url, _requests) = serve(|request| match request.method.as_str() {
+            "GET" => Response::ok(r#"<input name="csrfmiddlewaretoken" value="token">"#),
+            _ => Response::ok("<form>errors</form>"),
+        });
+
+        let settings = RoomSettings {
+            name: String::from("Race"),
+            passphrase: String::from("secret"),
+            hide_card: false,
+            ..Default::default()
+        };
+
+        assert!(RoomClient::new(&url).create_room(&settings, "[]").is_err());
+    }
+
+    #[test]
+    fn follow_room_mirrors_new_marks() {
+        let stop = Arc::new(AtomicBool::new(false));
+        let server_stop = stop.clone();
+        let mut feeds = 0;
+
+        let (url, requests) = serve(move |request| match request.path.as_str() {
+            "/api/join-room" => Response::ok("{}"),
+            "/room/AbC123/board" => Response::ok(
+                r#"[{"name": "Goal 1", "slot": "slot1", "colors": "red"},
+                    {"name": "Goal 5", "slot": "slot5", "colors": "blank"}]"#,
+            ),
+            "/room/AbC123/feed" => {
+                feeds += 1;
+                let old = r#"{"type": "goal", "player": {"name": "Alice"}, "player_color": "red",
+                    "square": {"name": "Goal 1", "slot": "slot1", "colors": "red"},
+                    "remove": false, "timestamp": 1.0}"#;
+                if feeds == 1 {
+                    return Response::ok(&format!(r#"{{"events": [{}]}}"#, old));
+                }
+
+                // Events of a single timestamp are all new, chat is left out.
+                server_stop.store(true, Ordering::Relaxed);
+                Response::ok(&format!(
+                    r#"{{"events": [{},
+                    {{"type": "goal", "player": {{"name": "Alice"}}, "player_color": "red",
+                      "square": {{"name": "Goal 5", "slot": "slot5", "colors": "red"}},
+                      "remove": false, "timestamp": 2.0}},
+                    {{"type": "goal", "player": {{"name": "Bob"}}, "player_color": "blue",
+                      "square": {{"name": "Goal 1", "slot": "slot1", "colors": "blank"}},
+                      "remove": true, "timestamp": 2.0}},
+                    {{"type": "chat", "player": {{"name": "Bob"}}, "text": "gg",
+                      "timestamp": 3.0}}]}}"#,
+                    old
+                ))
+            }
+            _ => Response {
+                status: 404,
+                headers: vec![],
+                body: String::new(),
+            },
+        });
+
+        let log_path =
+            std::env::temp_dir().join(format!("bingosync-gen-watch-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&log_path);
+
+        let settings = WatchSettings {
+            server: url.to_owned(),
+            room: format!("{}/room/AbC123", url),
+            passphrase: String::from("secret"),
+            nickname: String::from("observer"),
+            log_path: Some(log_path.to_owned()),
+            poll_interval: Duration::from_millis(1),
+        };
+        let (sender, receiver) = mpsc::channel();
+
+        follow_room(&settings, &sender, &|| {}, &stop).unwrap();
+
+        let join = requests.recv().unwrap();
+        assert_eq!(join.path, "/api/join-room");
+        let join: serde_json::Value = serde_json::from_str(&join.body).unwrap();
+        assert_eq!(
+            join,
+            serde_json::json!({
+                "room": "AbC123",
+                "nickname": "observer",
+                "password": "secret",
+                "is_spectator": true,
+            })
+        );
+
+        let messages = receiver.try_iter().collect::<Vec<_>>();
+        let WatchMessage::Board(squares) = &messages[0] else {
+            panic!("Expected the board first");
+        };
+        assert_eq!(
+            squares.iter().map(Square::index).collect::<Vec<_>>(),
+            [Some(0), Some(4)]
+        );
+
+        let goals = messages[1..]
+            .iter()
+            .map(|message| match message {
+                WatchMessage::Goal(event) => {
+                    (event.square.as_ref().unwrap().slot.as_str(), event.remove)
+                }
+                _ => panic!("Expected only goal events"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(goals, [("slot5", false), ("slot1", true)]);
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let _ = std::fs::remove_file(&log_path);
+        assert_eq!(
+            log.lines().collect::<Vec<_>>(),
+            [
+                "2.000\tAlice\tred\tmarked\tslot5\tGoal 5",
+                "2.000\tBob\tblue\tcleared\tslot1\tGoal 1",
+            ]
+        );
+    }
+
+    /// Watches a room whose feed gives the `statuses` in turn after the first poll,
+    /// 200 adds a goal event and stops the watch. Returns the result, messages and feed polls.
+    fn watch_with_feed(statuses: &'static [u16]) -> (anyhow::Result<()>, Vec<WatchMessage>, usize) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let server_stop = stop.clone();
+        // Counted before responding, the served requests are only sent after the response.
+        let polls = Arc::new(AtomicUsize::new(0));
+        let server_polls = polls.clone();
+
+        let (url, _requests) = serve(move |request| match request.path.as_str() {
+            "/room/AbC123/feed" => {
+                let poll = server_polls.fetch_add(1, Ordering::Relaxed);
+                if poll == 0 {
+                    return Response::ok(r#"{"events": []}"#);
+                }
+
+                let status = statuses[(poll - 1).min(statuses.len() - 1)];
+                if status != 200 {
+                    return Response {
+                        status,
+                        headers: vec![],
+                        body: String::new(),
+                    };
+                }
+
+                server_stop.store(true, Ordering::Relaxed);
+                Response::ok(
+                    r#"{"events": [{"type": "goal", "player": {"name": "Alice"},
+                        "player_color": "red",
+                        "square": {"name": "Goal 5", "slot": "slot5", "colors": "red"},
+                        "remove": false, "timestamp": 2.0}]}"#,
+                )
+            }
+            "/room/AbC123/board" => Response::ok("[]"),
+            _ => Response::ok("{}"),
+        });
+
+        let settings = WatchSettings {
+            server: url.to_owned(),
+            room: String::from("AbC123"),
+            passphrase: String::from("secret"),
+            nickname: String::from("observer"),
+            log_path: None,
+            poll_interval: Duration::from_millis(1),
+        };
+        let (sender, receiver) = mpsc::channel();
+
+        let result = follow_room(&settings, &sender, &|| {}, &stop);
+
+        (
+            result,
+            receiver.try_iter().collect(),
+            polls.load(Ordering::Relaxed),
+        )
+    }
+
+    #[test]
+    fn follow_room_retries_failed_polls() {
+        let (result, messages, polls) = watch_with_feed(&[500, 502, 500, 200]);
+
+        result.unwrap();
+        assert_eq!(polls, 5);
+        assert!(matches!(messages.last(), Some(WatchMessage::Goal(_))));
+    }
+
+    #[test]
+    fn follow_room_gives_up_after_failures() {
+        let (result, _, polls) = watch_with_feed(&[500]);
+
+        assert!(result.is_err());
+        assert_eq!(polls, 1 + MAX_POLL_FAILURES as usize);
+    }
+
+    #[test]
+    fn follow_room_stops_when_refused() {
+        let (result, _, polls) = watch_with_feed(&[403, 200]);
+
+        assert!(result.is_err());
+        assert_eq!(polls, 2);
+    }
+}
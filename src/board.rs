@@ -0,0 +1,406 @@
+use std::{collections::BTreeMap, fmt};
+
+use anyhow::{anyhow, bail};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
+
+use crate::card::CardRow;
+
+/// The board is always stored as 5x5, smaller fields use a part of it.
+pub(crate) const BOARD_CELLS: usize = 25;
+
+/// Center cell of the stored board, shared by the 3x3 and 5x5 fields.
+pub(crate) const CENTER_CELL: usize = 12;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub(crate) enum FieldSize {
+    Three = 3,
+    Four,
+    #[default]
+    Five,
+}
+
+impl fmt::Display for FieldSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FieldSize {
+    /// 3x3 sits in the middle of the 5x5 board, 4x4 in the top left corner.
+    fn offset(self) -> usize {
+        match self {
+            FieldSize::Three => 1,
+            FieldSize::Four | FieldSize::Five => 0,
+        }
+    }
+
+    /// Board indices used by this field size, row by row.
+    pub(crate) fn cells(self) -> Vec<usize> {
+        let size = self as usize;
+        let offset = self.offset();
+
+        (0..size)
+            .flat_map(|r| (0..size).map(move |c| (r + offset) * 5 + c + offset))
+            .collect()
+    }
+
+    /// Rows, columns and diagonals of the field with their board indices.
+    pub(crate) fn lines(self) -> Vec<(String, Vec<usize>)> {
+        let size = self as usize;
+        let cells = self.cells();
+
+        let rows = (0..size).map(|r| {
+            (
+                format!("Row {}", r + 1),
+                (0..size).map(|c| cells[r * size + c]).collect(),
+            )
+        });
+        let cols = (0..size).map(|c| {
+            (
+                format!("Col {}", c + 1),
+                (0..size).map(|r| cells[r * size + c]).collect(),
+            )
+        });
+        let diagonals = [
+            (
+                String::from("TL-BR"),
+                (0..size).map(|i| cells[i * size + i]).collect(),
+            ),
+            (
+                String::from("BL-TR"),
+                (0..size)
+                    .map(|i| cells[(size - 1 - i) * size + i])
+                    .collect(),
+            ),
+        ];
+
+        rows.chain(cols).chain(diagonals).collect()
+    }
+
+    /// 4x4 has no single center cell.
+    pub(crate) fn has_center(self) -> bool {
+        self != FieldSize::Four
+    }
+}
+
+/// Picks `count` distinct indices into the `pool`.
+///
+/// Weighted mode uses card weight as a relative chance to be picked. `quotas` is the least
+/// number of cards of a category, those are picked first and the rest from the whole pool.
+pub(crate) fn pick(
+    pool: &[&CardRow],
+    count: usize,
+    weighted: bool,
+    quotas: &BTreeMap<String, usize>,
+    rng: &mut impl Rng,
+) -> anyhow::Result<Vec<usize>> {
+    let quotas = quotas
+        .iter()
+        .filter(|(_, quota)| **quota > 0)
+        .collect::<Vec<_>>();
+
+    // Without quotas the picks stay the same for a seed as before quotas existed.
+    if quotas.is_empty() {
+        return pick_from(pool, count, weighted, rng);
+    }
+
+    let needed = quotas.iter().map(|(_, quota)| **quota).sum::<usize>();
+    if needed > count {
+        bail!(
+            "Quotas need {} cards, the board has {} cells",
+            needed,
+            count
+        );
+    }
+
+    let mut picked = vec![];
+
+    for (category, quota) in quotas {
+        let indices = (0..pool.len())
+            .filter(|idx| pool[*idx].0 == *category)
+            .collect::<Vec<_>>();
+        let cards = indices.iter().map(|idx| pool[*idx]).collect::<Vec<_>>();
+
+        let category_picks = pick_from(&cards, *quota, weighted, rng).map_err(|_| {
+            anyhow!(
+                "Not enough cards in {} for its quota of {}",
+                category,
+                quota
+            )
+        })?;
+        picked.extend(category_picks.into_iter().map(|idx| indices[idx]));
+    }
+
+    let rest = (0..pool.len())
+        .filter(|idx| !picked.contains(idx))
+        .collect::<Vec<_>>();
+    let cards = rest.iter().map(|idx| pool[*idx]).collect::<Vec<_>>();
+
+    picked.extend(
+        pick_from(&cards, count - picked.len(), weighted, rng)?
+            .into_iter()
+            .map(|idx| rest[idx]),
+    );
+    // Quota cards would always end up in the first cells otherwise.
+    picked.shuffle(rng);
+
+    Ok(picked)
+}
+
+fn pick_from(
+    pool: &[&CardRow],
+    count: usize,
+    weighted: bool,
+    rng: &mut impl Rng,
+) -> anyhow::Result<Vec<usize>> {
+    if pool.len() < count {
+        bail!("Not Enough Samples");
+    }
+
+    if !weighted {
+        let mut result = (0..pool.len()).choose_multiple(rng, count);
+        result.shuffle(rng);
+        return Ok(result);
+    }
+
+    // Cards with zero weight are never picked, so they don't count.
+    let candidates = (0..pool.len())
+        .filter(|idx| pool[*idx].2 > 0.0)
+        .collect::<Vec<_>>();
+    if candidates.len() < count {
+        bail!("Not Enough Samples");
+    }
+
+    // Picked cards are left out of the next draws, so any weight ratio takes `count` draws.
+    let mut result = candidates
+        .choose_multiple_weighted(rng, count, |idx| pool[*idx].2)?
+        .copied()
+        .collect::<Vec<_>>();
+    result.shuffle(rng);
+
+    Ok(result)
+}
+
+/// Picks a distinct card for each of the `cells` from the `pool`.
+pub(crate) fn generate(
+    pool: &[&CardRow],
+    cells: &[usize],
+    weighted: bool,
+    quotas: &BTreeMap<String, usize>,
+    rng: &mut impl Rng,
+) -> anyhow::Result<Vec<(usize, String)>> {
+    Ok(cells
+        .iter()
+        .copied()
+        .zip(
+            pick(pool, cells.len(), weighted, quotas, rng)?
+                .into_iter()
+                .map(|idx| pool[idx].1.to_owned()),
+        )
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Transform {
+    Rotate,
+    MirrorHorizontal,
+    MirrorVertical,
+    Transpose,
+    ShuffleRows,
+    ShuffleColumns,
+}
+
+impl Transform {
+    pub(crate) const ALL: [Transform; 6] = [
+        Transform::Rotate,
+        Transform::MirrorHorizontal,
+        Transform::MirrorVertical,
+        Transform::Transpose,
+        Transform::ShuffleRows,
+        Transform::ShuffleColumns,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Transform::Rotate => "Rotate 90°",
+            Transform::MirrorHorizontal => "Mirror Horizontally",
+            Transform::MirrorVertical => "Mirror Vertically",
+            Transform::Transpose => "Transpose",
+            Transform::ShuffleRows => "Shuffle Rows",
+            Transform::ShuffleColumns => "Shuffle Columns",
+        }
+    }
+
+    /// Rearranges the cards of the field on the board.
+    ///
+    /// Rotations, mirrors and transpose turn every line into another line, so the
+    /// set of line totals stays the same. Shuffles keep rows and columns intact,
+    /// but not the diagonals. With `keep_center` shuffles don't move the middle
+    /// row/column of 3x3 and 5x5, so the free space stays in place.
+    pub(crate) fn apply(
+        self,
+        board: &mut [String; BOARD_CELLS],
+        size: FieldSize,
+        keep_center: bool,
+        rng: &mut impl Rng,
+    ) {
+        let n = size as usize;
+        let mut order: Vec<usize> = (0..n).collect();
+
+        if matches!(self, Transform::ShuffleRows | Transform::ShuffleColumns) {
+            if keep_center && size.has_center() {
+                order.remove(n / 2);
+                order.shuffle(rng);
+                order.insert(n / 2, n / 2);
+            } else {
+                order.shuffle(rng);
+            }
+        }
+
+        let source = |r: usize, c: usize| match self {
+            Transform::Rotate => (n - 1 - c, r),
+            Transform::MirrorHorizontal => (r, n - 1 - c),
+            Transform::MirrorVertical => (n - 1 - r, c),
+            Transform::Transpose => (c, r),
+            Transform::ShuffleRows => (order[r], c),
+            Transform::ShuffleColumns => (r, order[c]),
+        };
+
+        let cells = size.cells();
+        let old: Vec<String> = cells.iter().map(|idx| board[*idx].to_owned()).collect();
+
+        for r in 0..n {
+            for c in 0..n {
+                let (src_r, src_c) = source(r, c);
+                board[cells[r * n + c]] = old[src_r * n + src_c].to_owned();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn cards() -> Vec<CardRow> {
+        (0..20)
+            .map(|i| {
+                let category = if i < 3 { "Rare" } else { "Common" };
+                CardRow(category.into(), format!("Goal {}", i), 1.0, true)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quotas_are_filled() {
+        let cards = cards();
+        let pool = cards.iter().collect::<Vec<_>>();
+        let quotas = BTreeMap::from([(String::from("Rare"), 2)]);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for weighted in [false, true] {
+            for _ in 0..100 {
+                let picked = pick(&pool, 9, weighted, &quotas, &mut rng).unwrap();
+                let rare = picked.iter().filter(|idx| pool[**idx].0 == "Rare").count();
+
+                assert_eq!(picked.iter().unique().count(), 9);
+                assert!(rare >= 2);
+            }
+        }
+    }
+
+    #[test]
+    fn impossible_quotas_fail() {
+        let cards = cards();
+        let pool = cards.iter().collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let quotas = BTreeMap::from([(String::from("Rare"), 4)]);
+        assert!(pick(&pool, 9, false, &quotas, &mut rng).is_err());
+
+        let quotas = BTreeMap::from([(String::from("Common"), 10)]);
+        assert!(pick(&pool, 9, false, &quotas, &mut rng).is_err());
+    }
+
+    /// Cards of each line as a set, ignoring which line they are in.
+    fn line_sets(
+        board: &[String; BOARD_CELLS],
+        lines: &[(String, Vec<usize>)],
+    ) -> Vec<Vec<String>> {
+        lines
+            .iter()
+            .map(|(_, cells)| {
+                cells
+                    .iter()
+                    .map(|idx| board[*idx].to_owned())
+                    .sorted()
+                    .collect()
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn transforms_keep_lines() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let board: [String; BOARD_CELLS] = core::array::from_fn(|idx| format!("Goal {}", idx));
+
+        for size in [FieldSize::Three, FieldSize::Four, FieldSize::Five] {
+            let lines = size.lines();
+            // Shuffles keep rows and columns, `lines` has them before the diagonals.
+            let rows_and_columns = &lines[..2 * size as usize];
+
+            for transform in Transform::ALL {
+                for keep_center in [false, true] {
+                    let mut transformed = board.clone();
+                    transform.apply(&mut transformed, size, keep_center, &mut rng);
+
+                    let kept = match transform {
+                        Transform::ShuffleRows | Transform::ShuffleColumns => rows_and_columns,
+                        _ => &lines[..],
+                    };
+                    assert_eq!(
+                        line_sets(&transformed, kept),
+                        line_sets(&board, kept),
+                        "{:?} {}",
+                        transform,
+                        size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn extreme_weights_finish() {
+        let mut cards = vec![CardRow("Common".into(), "Heavy".into(), 1e12, true)];
+        cards.extend((0..50).map(|i| CardRow("Common".into(), format!("Goal {}", i), 1e-12, true)));
+        let pool = cards.iter().collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..1000 {
+            let picked = pick(&pool, 25, true, &BTreeMap::new(), &mut rng).unwrap();
+
+            assert_eq!(picked.iter().unique().count(), 25);
+            assert!(picked.contains(&0));
+        }
+    }
+
+    #[test]
+    fn no_quotas_keep_seeded_picks() {
+        let cards = cards();
+        let pool = cards.iter().collect::<Vec<_>>();
+        let quotas = BTreeMap::from([(String::from("Rare"), 0)]);
+
+        assert_eq!(
+            pick(&pool, 9, true, &quotas, &mut StdRng::seed_from_u64(7)).unwrap(),
+            pick_from(&pool, 9, true, &mut StdRng::seed_from_u64(7)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,7 @@
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct CardRow(
+    #[serde(rename = "category")] pub(crate) String,
+    #[serde(rename = "text")] pub(crate) String,
+    #[serde(rename = "weight")] pub(crate) f64,
+    #[serde(rename = "enabled")] pub(crate) bool,
+);
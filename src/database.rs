@@ -0,0 +1,351 @@
+//! Reading and writing the card database, the format is picked by file extension.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::bail;
+
+use crate::{board::FieldSize, card::CardRow};
+
+/// Version of the JSON/TOML layout, bump it when the layout changes.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+pub(crate) const EXTENSIONS: [&str; 3] = ["csv", "json", "toml"];
+
+/// Database kept inside the app, with the board settings used for its game.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct NamedDatabase {
+    pub(crate) name: String,
+    pub(crate) cards: Vec<CardRow>,
+    pub(crate) settings: DatabaseSettings,
+}
+
+/// Board settings applied when the database is selected.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct DatabaseSettings {
+    pub(crate) field_size: FieldSize,
+    pub(crate) free_space: bool,
+    pub(crate) free_space_text: String,
+    /// Least number of cards of a category on each board, see `board::pick`.
+    pub(crate) quotas: BTreeMap<String, usize>,
+}
+
+impl Default for NamedDatabase {
+    fn default() -> Self {
+        Self {
+            name: String::from("Default"),
+            cards: vec![],
+            settings: DatabaseSettings::default(),
+        }
+    }
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            field_size: FieldSize::default(),
+            free_space: false,
+            free_space_text: String::from("FREE"),
+            quotas: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct DatabaseFile {
+    version: u32,
+    #[serde(default)]
+    cards: Vec<CardRecord>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct CardRecord {
+    #[serde(default)]
+    category: String,
+    text: String,
+    #[serde(default = "default_weight")]
+    weight: f64,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl From<&CardRow> for CardRecord {
+    fn from(row: &CardRow) -> Self {
+        Self {
+            category: row.0.to_owned(),
+            text: row.1.to_owned(),
+            weight: row.2,
+            enabled: row.3,
+        }
+    }
+}
+
+impl From<CardRecord> for CardRow {
+    fn from(record: CardRecord) -> Self {
+        CardRow(record.category, record.text, record.weight, record.enabled)
+    }
+}
+
+#[derive(PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+    Toml,
+}
+
+impl Format {
+    fn of(path: &Path) -> anyhow::Result<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("csv") => Ok(Format::Csv),
+            Some("json") => Ok(Format::Json),
+            Some("toml") => Ok(Format::Toml),
+            Some(ext) => bail!("Unsupported database format: .{}", ext),
+        }
+    }
+}
+
+pub(crate) fn load(path: &Path) -> anyhow::Result<Vec<CardRow>> {
+    let file = match Format::of(path)? {
+        Format::Csv => {
+            let reader = BufReader::new(File::open(path)?);
+            let mut dataset = csv::Reader::from_reader(reader);
+
+            return Ok(dataset.deserialize().collect::<Result<Vec<CardRow>, _>>()?);
+        }
+        Format::Json => serde_json::from_str::<DatabaseFile>(&fs::read_to_string(path)?)?,
+        Format::Toml => toml::from_str::<DatabaseFile>(&fs::read_to_string(path)?)?,
+    };
+
+    if file.version > SCHEMA_VERSION {
+        bail!(
+            "Database version {} is newer than supported {}",
+            file.version,
+            SCHEMA_VERSION
+        );
+    }
+
+    Ok(file.cards.into_iter().map(CardRow::from).collect())
+}
+
+pub(crate) fn save<'a>(
+    path: &Path,
+    cards: impl Iterator<Item = &'a CardRow>,
+) -> anyhow::Result<()> {
+    let format = Format::of(path)?;
+
+    if format == Format::Csv {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["category", "text", "weight", "enabled"])?; // Header
+
+        for record in cards {
+            writer.serialize(record)?;
+        }
+
+        writer.flush()?;
+        return Ok(());
+    }
+
+    let file = DatabaseFile {
+        version: SCHEMA_VERSION,
+        cards: cards.map(CardRecord::from).collect(),
+    };
+    let content = if format == Format::Json {
+        serde_json::to_string_pretty(&file)?
+    } else {
+        toml::to_string_pretty(&file)?
+    };
+
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+/// Database file that is read again when it changes on disk, checked by modification time.
+///
+/// Only a hash of the synced cards is kept, the link is saved with the app state and the
+/// cards are already in the database.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub(crate) struct FileLink {
+    pub(crate) path: PathBuf,
+    /// Modification time of the file when it was last read, `None` reads it on the next `poll`.
+    pub(crate) modified: Option<SystemTime>,
+    /// `cards_hash` of the cards as they were last read from or written to the file.
+    pub(crate) synced: u64,
+}
+
+impl FileLink {
+    /// `synced` are the cards the file had when it was picked.
+    pub(crate) fn new(path: PathBuf, synced: &[CardRow]) -> Self {
+        Self {
+            path,
+            modified: None,
+            synced: cards_hash(synced),
+        }
+    }
+
+    /// Cards in the file if it changed since the last poll.
+    pub(crate) fn poll(&mut self) -> anyhow::Result<Option<Vec<CardRow>>> {
+        let modified = fs::metadata(&self.path)?.modified()?;
+        if Some(modified) == self.modified {
+            return Ok(None);
+        }
+
+        // A file caught in the middle of a write fails to load, so it is read again next time.
+        let cards = load(&self.path)?;
+        self.modified = Some(modified);
+
+        Ok(Some(cards))
+    }
+
+    pub(crate) fn write(&mut self, cards: &[CardRow]) -> anyhow::Result<()> {
+        save(&self.path, cards.iter())?;
+        self.modified = Some(fs::metadata(&self.path)?.modified()?);
+        self.synced = cards_hash(cards);
+
+        Ok(())
+    }
+}
+
+/// FNV-1a hash of the cards, unlike `DefaultHasher` it stays the same across Rust versions
+/// so it can be saved.
+pub(crate) fn cards_hash<'a>(cards: impl IntoIterator<Item = &'a CardRow>) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    for card in cards {
+        // Lengths keep `("ab", "c")` and `("a", "bc")` apart.
+        write(&card.0.len().to_le_bytes());
+        write(card.0.as_bytes());
+        write(&card.1.len().to_le_bytes());
+        write(card.1.as_bytes());
+        write(&card.2.to_bits().to_le_bytes());
+        write(&[u8::from(card.3)]);
+    }
+
+    hash
+}
+
+/// Difference of a single card between two versions of a database.
+pub(crate) enum CardChange {
+    Added(CardRow),
+    Removed(CardRow),
+    Modified { old: CardRow, new: CardRow },
+}
+
+/// Cards are matched by text, changes follow the order of `new` with removed cards last.
+pub(crate) fn diff(old: &[CardRow], new: &[CardRow]) -> Vec<CardChange> {
+    let old_cards = old
+        .iter()
+        .rev()
+        .map(|card| (card.1.as_str(), card))
+        .collect::<HashMap<_, _>>();
+    let new_cards = new
+        .iter()
+        .rev()
+        .map(|card| (card.1.as_str(), card))
+        .collect::<HashMap<_, _>>();
+
+    let mut changes = vec![];
+
+    for card in new {
+        match old_cards.get(card.1.as_str()) {
+            None => changes.push(CardChange::Added(card.to_owned())),
+            Some(old) if *old != card => changes.push(CardChange::Modified {
+                old: (*old).to_owned(),
+                new: card.to_owned(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for card in old {
+        if !new_cards.contains_key(card.1.as_str()) {
+            changes.push(CardChange::Removed(card.to_owned()));
+        }
+    }
+
+    changes
+}
+
+/// Applies changes made by `diff` to `cards`.
+pub(crate) fn apply<'a>(cards: &mut Vec<CardRow>, changes: impl Iterator<Item = &'a CardChange>) {
+    for change in changes {
+        match change {
+            CardChange::Added(card) => cards.push(card.to_owned()),
+            CardChange::Removed(card) => {
+                if let Some(idx) = cards.iter().position(|item| item == card) {
+                    cards.remove(idx);
+                }
+            }
+            CardChange::Modified { old, new } => {
+                if let Some(item) = cards.iter_mut().find(|item| *item == old) {
+                    *item = new.to_owned();
+                }
+            }
+        }
+    }
+}
+
+/// Counts of cards that differ between two versions of a database, matched by text.
+pub(crate) struct Changes {
+    pub(crate) added: usize,
+    pub(crate) removed: usize,
+    pub(crate) changed: usize,
+}
+
+impl Changes {
+    pub(crate) fn between(old: &[CardRow], new: &[CardRow]) -> Self {
+        let mut changes = Self {
+            added: 0,
+            removed: 0,
+            changed: 0,
+        };
+
+        for change in diff(old, new) {
+            match change {
+                CardChange::Added(_) => changes.added += 1,
+                CardChange::Removed(_) => changes.removed += 1,
+                CardChange::Modified { .. } => changes.changed += 1,
+            }
+        }
+
+        changes
+    }
+}
+
+impl fmt::Display for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added, self.removed, self.changed
+        )
+    }
+}
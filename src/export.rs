@@ -0,0 +1,389 @@
+//! Text based board exports: standalone HTML page, Markdown table and clipboard formats.
+
+use anyhow::bail;
+
+use crate::board::{FieldSize, BOARD_CELLS};
+
+/// Extra text and styling shared by the image, HTML and PDF exports.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct ExportOptions {
+    pub(crate) title: String,
+    pub(crate) footer: String,
+    pub(crate) dark: bool,
+    /// HTML pages get a script to mark cells by clicking them.
+    pub(crate) clickable: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            title: String::from(""),
+            footer: String::from(""),
+            dark: true,
+            clickable: true,
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace('\n', "<br>")
+}
+
+fn rows(board: &[String; BOARD_CELLS], size: FieldSize) -> Vec<Vec<&str>> {
+    size.cells()
+        .chunks(size as usize)
+        .map(|row| row.iter().map(|idx| board[*idx].as_str()).collect())
+        .collect()
+}
+
+const HTML_STYLE: &str = r#"
+body { font-family: sans-serif; display: flex; flex-direction: column; align-items: center; }
+body.dark { background: #1b1b1b; color: #f0f0f0; }
+table { border-collapse: collapse; table-layout: fixed; }
+td { width: 120px; height: 120px; padding: 6px; border: 1px solid #808080; text-align: center; vertical-align: middle; font-size: 16px; }
+body.dark td { background: #000; }
+body:not(.dark) td { background: #f2f2f2; }
+td.marked { background: #31d814 !important; color: #000; }
+td.clickable { cursor: pointer; user-select: none; }
+"#;
+
+const HTML_SCRIPT: &str = r#"
+document.querySelectorAll("td").forEach(function (cell) {
+  cell.classList.add("clickable");
+  cell.addEventListener("click", function () { cell.classList.toggle("marked"); });
+});
+"#;
+
+pub(crate) fn to_html(
+    board: &[String; BOARD_CELLS],
+    size: FieldSize,
+    options: &ExportOptions,
+) -> String {
+    let title = if options.title.is_empty() {
+        "Bingo Board"
+    } else {
+        options.title.as_str()
+    };
+
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", escape_html(title)));
+    out.push_str(&format!("<style>{}</style>\n</head>\n", HTML_STYLE));
+    out.push_str(if options.dark {
+        "<body class=\"dark\">\n"
+    } else {
+        "<body>\n"
+    });
+
+    if !options.title.is_empty() {
+        out.push_str(&format!("<h1>{}</h1>\n", escape_html(&options.title)));
+    }
+
+    out.push_str("<table>\n");
+    for row in rows(board, size) {
+        out.push_str("<tr>");
+        for cell in row {
+            out.push_str(&format!("<td>{}</td>", escape_html(cell)));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+
+    if !options.footer.is_empty() {
+        out.push_str(&format!("<p>{}</p>\n", escape_html(&options.footer)));
+    }
+    if options.clickable {
+        out.push_str(&format!("<script>{}</script>\n", HTML_SCRIPT));
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', "<br>")
+}
+
+pub(crate) fn to_markdown(board: &[String; BOARD_CELLS], size: FieldSize) -> String {
+    let n = size as usize;
+    let mut out = String::new();
+
+    // Markdown tables need a header, the board has none so it is left blank.
+    out.push_str(&format!("|{}\n", " |".repeat(n)));
+    out.push_str(&format!("|{}\n", "---|".repeat(n)));
+
+    for row in rows(board, size) {
+        out.push('|');
+        for cell in row {
+            out.push_str(&format!(" {} |", escape_markdown(cell)));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CopyFormat {
+    Json,
+    JsonPretty,
+    Lines,
+    Grid,
+    Markdown,
+}
+
+impl CopyFormat {
+    pub(crate) const ALL: [CopyFormat; 5] = [
+        CopyFormat::Json,
+        CopyFormat::JsonPretty,
+        CopyFormat::Lines,
+        CopyFormat::Grid,
+        CopyFormat::Markdown,
+    ];
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            CopyFormat::Json => "BingoSync JSON",
+            CopyFormat::JsonPretty => "BingoSync JSON (Pretty)",
+            CopyFormat::Lines => "Plain List",
+            CopyFormat::Grid => "Tab Separated Grid",
+            CopyFormat::Markdown => "Markdown",
+        }
+    }
+
+    /// JSON keeps the whole board like `Save` does, the rest only have the field cells.
+    pub(crate) fn format(&self, board: &[String; BOARD_CELLS], size: FieldSize) -> String {
+        // Multiline goals would break the line based formats.
+        let single_line = |text: &str| text.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+
+        match self {
+            CopyFormat::Json | CopyFormat::JsonPretty => {
+                let cards = board
+                    .iter()
+                    .map(|name| {
+                        serde_json::json!({ "name": if name.is_empty() { " " } else { name } })
+                    })
+                    .collect::<Vec<_>>();
+
+                if *self == CopyFormat::Json {
+                    serde_json::Value::from(cards).to_string()
+                } else {
+                    serde_json::to_string_pretty(&cards).unwrap_or_default()
+                }
+            }
+            CopyFormat::Lines => size
+                .cells()
+                .iter()
+                .map(|idx| single_line(&board[*idx]).replace('\t', " "))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            CopyFormat::Grid => rows(board, size)
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| single_line(cell).replace('\t', " "))
+                        .collect::<Vec<_>>()
+                        .join("\t")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            CopyFormat::Markdown => to_markdown(board, size),
+        }
+    }
+}
+
+/// Reads a board copied in any of the `CopyFormat`s, the format is guessed from the text.
+///
+/// 9 or 16 cells pick the matching field size, so do 25 cells except for JSON. JSON always has
+/// the whole board, so the size is `None` and the current field size is kept.
+pub(crate) fn parse_board(
+    text: &str,
+) -> anyhow::Result<(Option<FieldSize>, [String; BOARD_CELLS])> {
+    let json = serde_json::from_str::<Vec<serde_json::Value>>(text.trim()).ok();
+    let is_json = json.is_some();
+
+    let cells: Vec<String> = match json {
+        Some(values) => values
+            .into_iter()
+            .map(|value| match value {
+                serde_json::Value::String(name) => name,
+                value => value["name"].as_str().unwrap_or_default().to_owned(),
+            })
+            .collect(),
+        None if is_markdown(text) => parse_markdown(text.trim()),
+        None => parse_lines(text),
+    };
+
+    let cells = cells
+        .into_iter()
+        .map(|cell| cell.trim().to_owned())
+        .collect::<Vec<String>>();
+
+    let size = match cells.len() {
+        9 => FieldSize::Three,
+        16 => FieldSize::Four,
+        BOARD_CELLS => FieldSize::Five,
+        count => bail!("Expected 9, 16 or 25 cells, got {}", count),
+    };
+
+    let mut board: [String; BOARD_CELLS] = core::array::from_fn(|_idx| String::from(""));
+    if cells.len() == BOARD_CELLS {
+        board = core::array::from_fn(|idx| cells[idx].to_owned());
+    } else {
+        for (idx, cell) in size.cells().into_iter().zip(cells) {
+            board[idx] = cell;
+        }
+    }
+
+    Ok(((!is_json || size != FieldSize::Five).then_some(size), board))
+}
+
+/// Markdown tables have a `---|` separator row, a list of goals starting with `|` doesn't.
+fn is_markdown(text: &str) -> bool {
+    text.lines().any(|line| {
+        line.contains("---|")
+            && line
+                .chars()
+                .all(|ch| matches!(ch, '|' | '-' | ':' | ' ' | '\t'))
+    })
+}
+
+/// Cells of the plain list or the tab separated grid.
+///
+/// Empty first and last cells are kept, a trailing line break is only dropped when the
+/// cell count doesn't fit a field without it.
+fn parse_lines(text: &str) -> Vec<String> {
+    let mut lines = text
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect::<Vec<_>>();
+    let split = |lines: &[&str]| -> Vec<String> {
+        if text.contains('\t') {
+            lines
+                .iter()
+                .flat_map(|line| line.split('\t').map(str::to_owned))
+                .collect()
+        } else {
+            lines.iter().map(|line| (*line).to_owned()).collect()
+        }
+    };
+
+    loop {
+        let cells = split(&lines);
+        let fits = [9, 16, BOARD_CELLS].contains(&cells.len());
+
+        if fits || !lines.last().is_some_and(|line| line.trim().is_empty()) {
+            return cells;
+        }
+        lines.pop();
+    }
+}
+
+/// Cells of a Markdown table, everything up to the `---` separator is the header.
+fn parse_markdown(text: &str) -> Vec<String> {
+    let rows = text
+        .lines()
+        .map(|line| {
+            let line = line.trim().trim_start_matches('|');
+            let line = line.strip_suffix('|').unwrap_or(line);
+            let mut cells = vec![];
+            let mut cell = String::new();
+            let mut chars = line.chars().peekable();
+
+            while let Some(ch) = chars.next() {
+                match ch {
+                    '\\' if chars.peek() == Some(&'|') => cell.push(chars.next().unwrap_or('|')),
+                    '|' => cells.push(std::mem::take(&mut cell)),
+                    _ => cell.push(ch),
+                }
+            }
+            cells.push(cell);
+
+            cells
+                .into_iter()
+                .map(|cell| cell.trim().replace("<br>", "\n"))
+                .collect::<Vec<String>>()
+        })
+        .collect::<Vec<_>>();
+
+    let is_separator = |row: &Vec<String>| {
+        row.iter()
+            .all(|cell| !cell.is_empty() && cell.chars().all(|ch| matches!(ch, '-' | ':')))
+    };
+
+    match rows.iter().position(is_separator) {
+        Some(separator) => rows.into_iter().skip(separator + 1).flatten().collect(),
+        None => rows.into_iter().flatten().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Field cells are numbered, except the first and the last one which stay empty.
+    fn board(size: FieldSize) -> [String; BOARD_CELLS] {
+        let cells = size.cells();
+        let mut board: [String; BOARD_CELLS] = Default::default();
+
+        for (i, idx) in cells.iter().enumerate().skip(1).take(cells.len() - 2) {
+            board[*idx] = format!("Goal {}", i);
+        }
+
+        board
+    }
+
+    #[test]
+    fn copied_boards_paste_back() {
+        for size in [FieldSize::Three, FieldSize::Four, FieldSize::Five] {
+            let mut board = board(size);
+            board[size.cells()[2]] = String::from("Goal\twith a tab");
+
+            for format in CopyFormat::ALL {
+                let text = format.format(&board, size);
+                let (pasted_size, pasted) = parse_board(&text).unwrap();
+
+                // The line based formats split cells on tabs, so they copy them as spaces.
+                let mut expected = board.clone();
+                if matches!(format, CopyFormat::Lines | CopyFormat::Grid) {
+                    expected = expected.map(|cell| cell.replace('\t', " "));
+                }
+
+                assert_eq!(pasted, expected, "{:?} {}", format, size);
+                match format {
+                    CopyFormat::Json | CopyFormat::JsonPretty => assert_eq!(pasted_size, None),
+                    _ => assert_eq!(pasted_size, Some(size), "{:?}", format),
+                }
+
+                // Some clipboards add a line break at the end.
+                let (_, pasted) = parse_board(&format!("{}\r\n", text)).unwrap();
+                assert_eq!(pasted, expected, "{:?} {} with a line break", format, size);
+            }
+        }
+    }
+
+    #[test]
+    fn lists_starting_like_other_formats_paste() {
+        for first in ["[Optional] Beat the boss", "| Open the gate |"] {
+            let mut board = board(FieldSize::Three);
+            board[FieldSize::Three.cells()[0]] = String::from(first);
+
+            for format in [CopyFormat::Lines, CopyFormat::Grid] {
+                let text = format.format(&board, FieldSize::Three);
+
+                assert_eq!(
+                    parse_board(&text).unwrap(),
+                    (Some(FieldSize::Three), board.clone()),
+                    "{:?} {}",
+                    format,
+                    first
+                );
+            }
+        }
+    }
+}
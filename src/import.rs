@@ -0,0 +1,239 @@
+//! Importing tables with arbitrary columns, the user maps them to card fields.
+
+use std::path::Path;
+
+use calamine::{open_workbook_auto, Reader};
+
+use crate::card::CardRow;
+
+/// Delimiters recognized in CSV files with their names.
+pub(crate) const DELIMITERS: [(u8, &str); 3] =
+    [(b',', "Comma"), (b';', "Semicolon"), (b'\t', "Tab")];
+
+pub(crate) const SPREADSHEET_EXTENSIONS: [&str; 4] = ["xlsx", "xlsm", "xls", "ods"];
+
+/// Card fields in the order of `ColumnMapping::columns`.
+pub(crate) const FIELDS: [&str; 4] = ["category", "text", "weight", "enabled"];
+
+pub(crate) struct Sheet {
+    pub(crate) name: String,
+    pub(crate) headers: Vec<String>,
+    pub(crate) rows: Vec<Vec<String>>,
+}
+
+/// Column index for each of the `FIELDS`, missing columns get default values.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ColumnMapping {
+    pub(crate) columns: [Option<usize>; 4],
+}
+
+/// Import settings remembered for a CSV file.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub(crate) struct CsvMapping {
+    pub(crate) delimiter: u8,
+    pub(crate) mapping: ColumnMapping,
+    /// Header of each mapped column, so the mapping follows columns that moved.
+    #[serde(default)]
+    pub(crate) headers: [Option<String>; 4],
+}
+
+impl CsvMapping {
+    pub(crate) fn new(delimiter: u8, mapping: ColumnMapping, headers: &[String]) -> Self {
+        Self {
+            delimiter,
+            headers: mapping
+                .columns
+                .map(|column| column.and_then(|idx| headers.get(idx)).cloned()),
+            mapping,
+        }
+    }
+
+    /// Columns are found by header, the remembered index is used when the header is gone.
+    pub(crate) fn mapping(&self, headers: &[String]) -> ColumnMapping {
+        let mut columns = self.mapping.columns;
+
+        for (column, header) in columns.iter_mut().zip(self.headers.iter()) {
+            let found = header
+                .as_ref()
+                .and_then(|header| headers.iter().position(|other| other == header));
+
+            *column = found.or(column.filter(|idx| *idx < headers.len()));
+        }
+
+        ColumnMapping { columns }
+    }
+}
+
+impl ColumnMapping {
+    /// Every field has a column.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.columns.iter().all(Option::is_some)
+    }
+
+    /// Matches headers named like the fields, ignoring case.
+    pub(crate) fn guess(headers: &[String]) -> Self {
+        Self {
+            columns: FIELDS.map(|field| {
+                headers
+                    .iter()
+                    .position(|header| header.trim().eq_ignore_ascii_case(field))
+            }),
+        }
+    }
+
+    /// Builds a card from the row, rows without text are skipped.
+    pub(crate) fn card(&self, row: &[String], category: Option<&str>) -> Option<CardRow> {
+        let value = |field: usize| {
+            self.columns[field]
+                .and_then(|column| row.get(column))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+
+        let text = value(1)?;
+
+        Some(CardRow(
+            category.or(value(0)).unwrap_or_default().to_owned(),
+            text.to_owned(),
+            value(2)
+                .and_then(|weight| weight.parse::<f64>().ok())
+                .unwrap_or(1.0),
+            value(3).is_none_or(parse_enabled),
+        ))
+    }
+}
+
+fn parse_enabled(value: &str) -> bool {
+    !matches!(
+        value.to_ascii_lowercase().as_str(),
+        "false" | "0" | "no" | "n" | "off" | "disabled"
+    )
+}
+
+/// Reads every sheet of a workbook, the first row of a sheet is its header.
+pub(crate) fn read_spreadsheet(path: &Path) -> anyhow::Result<Vec<Sheet>> {
+    let mut workbook = open_workbook_auto(path)?;
+    let mut sheets = vec![];
+
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)?;
+        let mut rows = range.rows().map(|row| {
+            row.iter()
+                .map(|cell| cell.to_string())
+                .collect::<Vec<String>>()
+        });
+
+        sheets.push(Sheet {
+            name,
+            headers: rows.next().unwrap_or_default(),
+            rows: rows.collect(),
+        });
+    }
+
+    Ok(sheets)
+}
+
+/// Picks the delimiter that shows up the most in the header line, ignoring quoted parts.
+pub(crate) fn detect_delimiter(content: &str) -> u8 {
+    let header = content.lines().next().unwrap_or_default();
+    let mut counts = [0_usize; DELIMITERS.len()];
+    let mut quoted = false;
+
+    for byte in header.bytes() {
+        if byte == b'"' {
+            quoted = !quoted;
+        } else if !quoted {
+            if let Some(idx) = DELIMITERS
+                .iter()
+                .position(|(delimiter, _)| *delimiter == byte)
+            {
+                counts[idx] += 1;
+            }
+        }
+    }
+
+    DELIMITERS
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+        .map_or(b',', |((delimiter, _), _)| *delimiter)
+}
+
+/// Reads CSV content as a single sheet, the first record is its header.
+pub(crate) fn read_csv(name: &str, content: &str, delimiter: u8) -> anyhow::Result<Sheet> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let mut rows = vec![];
+
+    for record in reader.records() {
+        rows.push(record?.iter().map(str::to_owned).collect::<Vec<String>>());
+    }
+
+    let mut rows = rows.into_iter();
+
+    Ok(Sheet {
+        name: name.to_owned(),
+        headers: rows.next().unwrap_or_default(),
+        rows: rows.collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| (*name).to_owned()).collect()
+    }
+
+    #[test]
+    fn delimiters_are_detected() {
+        assert_eq!(detect_delimiter("category,text,weight\nA,B;C,1"), b',');
+        assert_eq!(detect_delimiter("category;text;weight\nA;B,C;1"), b';');
+        assert_eq!(detect_delimiter("category\ttext\tweight\nA\tB,C\t1"), b'\t');
+        // Delimiters in quoted headers don't count.
+        assert_eq!(detect_delimiter("\"a,b,c\";\"d,e\"\n"), b';');
+        assert_eq!(detect_delimiter("text\n"), b',');
+        assert_eq!(detect_delimiter(""), b',');
+    }
+
+    #[test]
+    fn mapping_follows_moved_columns() {
+        let old = headers(&["Goal", "Group", "Chance"]);
+        let mapping = ColumnMapping {
+            columns: [Some(1), Some(0), Some(2), None],
+        };
+        let remembered = CsvMapping::new(b',', mapping.clone(), &old);
+
+        assert_eq!(remembered.mapping(&old), mapping);
+
+        let new = headers(&["Chance", "Goal", "Notes", "Group"]);
+        assert_eq!(
+            remembered.mapping(&new).columns,
+            [Some(3), Some(1), Some(0), None]
+        );
+
+        // A renamed header keeps its index, columns past the end are dropped.
+        let renamed = headers(&["Goal", "Type"]);
+        assert_eq!(
+            remembered.mapping(&renamed).columns,
+            [Some(1), Some(0), None, None]
+        );
+    }
+
+    #[test]
+    fn mappings_without_headers_use_indices() {
+        let remembered: CsvMapping =
+            ron::from_str("(delimiter: 59, mapping: (columns: (Some(1), Some(0), None, None)))")
+                .unwrap();
+
+        assert_eq!(
+            remembered.mapping(&headers(&["a", "b", "c"])).columns,
+            [Some(1), Some(0), None, None]
+        );
+    }
+}
@@ -1,3 +1,21 @@
+mod analysis;
 mod app;
+#[cfg(feature = "bingosync")]
+mod bingosync;
+mod board;
+mod card;
+mod database;
+mod export;
+mod import;
+mod pdf;
+mod playtest;
+mod project;
+mod render;
+mod search;
+mod srl;
+mod stats;
+#[cfg(feature = "sqlite")]
+mod store;
+mod validate;
 
-pub use app::BingoSyncGen;
+pub use app::{BingoSyncGen, APP_NAME};
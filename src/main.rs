@@ -13,7 +13,7 @@ fn main() -> anyhow::Result<(), eframe::Error> {
     };
 
     eframe::run_native(
-        "BingoSync JSON Generator",
+        bingosync_gen::APP_NAME,
         native_options,
         Box::new(|cc| Box::new(bingosync_gen::BingoSyncGen::new(cc))),
     )
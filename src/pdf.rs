@@ -0,0 +1,306 @@
+//! Printable PDF cards, written by hand with the built-in Helvetica font so nothing is embedded.
+
+use std::path::Path;
+
+use crate::{
+    board::{FieldSize, BOARD_CELLS},
+    render::wrap_text,
+};
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 36.0;
+const PADDING: f32 = 4.0;
+const MIN_FONT_SIZE: f32 = 5.0;
+const TITLE_SCALE: f32 = 1.6;
+
+/// Boards that fit on a single page.
+pub(crate) const PER_PAGE: [usize; 3] = [1, 2, 4];
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct PdfOptions {
+    /// Export the board on screen instead of generating new ones.
+    pub(crate) current_board: bool,
+    pub(crate) boards: usize,
+    pub(crate) per_page: usize,
+    pub(crate) font_size: f32,
+    /// Side of a cell in points, boards are scaled down if they don't fit.
+    pub(crate) cell_size: f32,
+    pub(crate) seed: u64,
+    pub(crate) weighted: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            current_board: true,
+            boards: 1,
+            per_page: 1,
+            font_size: 12.0,
+            cell_size: 100.0,
+            seed: 0,
+            weighted: false,
+        }
+    }
+}
+
+/// Helvetica advance widths for ASCII 32..=126 in 1/1000 of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+fn text_width(size: f32, text: &str) -> f32 {
+    text.chars()
+        .map(|ch| match ch as u32 {
+            code @ 32..=126 => HELVETICA_WIDTHS[(code - 32) as usize],
+            _ => 556,
+        } as f32)
+        .sum::<f32>()
+        * size
+        / 1000.0
+}
+
+/// PDF string literal in WinAnsi, characters outside of Latin-1 become `?`.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for ch in text.chars() {
+        match ch {
+            '(' | ')' | '\\' => out.extend([b'\\', ch as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => out.push(ch as u32 as u8),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+    out
+}
+
+/// Content stream of a page, coordinates are taken from the top left corner.
+#[derive(Default)]
+struct Page {
+    content: Vec<u8>,
+}
+
+impl Page {
+    fn text(&mut self, size: f32, x: f32, y: f32, text: &str) {
+        // `y` is the top of the line, PDF wants the baseline.
+        let baseline = PAGE_HEIGHT - y - size * 0.8;
+        self.content
+            .extend(format!("BT /F1 {:.2} Tf {:.2} {:.2} Td ", size, x, baseline).as_bytes());
+        self.content.extend(pdf_string(text));
+        self.content.extend(b" Tj ET\n");
+    }
+
+    fn centered_text(&mut self, size: f32, center: f32, y: f32, text: &str) {
+        self.text(size, center - text_width(size, text) / 2.0, y, text);
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.content.extend(
+            format!(
+                "{:.2} {:.2} {:.2} {:.2} re S\n",
+                x,
+                PAGE_HEIGHT - y - height,
+                width,
+                height
+            )
+            .as_bytes(),
+        );
+    }
+
+    /// Draws a board with its caption centered in the given area.
+    fn board(
+        &mut self,
+        board: &[String; BOARD_CELLS],
+        size: FieldSize,
+        options: &PdfOptions,
+        title: &str,
+        caption: &str,
+        area: (f32, f32, f32, f32),
+    ) {
+        let (left, top, width, height) = area;
+        let n = size as usize;
+        let title_height = if title.is_empty() {
+            0.0
+        } else {
+            options.font_size * TITLE_SCALE * 1.5
+        };
+        let caption_height = options.font_size * 1.5;
+
+        let grid = options.cell_size * n as f32;
+        let scale = (width / grid)
+            .min((height - title_height - caption_height) / grid)
+            .min(1.0);
+        let cell = options.cell_size * scale;
+        let font_size = options.font_size * scale;
+        let grid = cell * n as f32;
+
+        let x = left + (width - grid) / 2.0;
+        let y = top + (height - grid - title_height - caption_height) / 2.0;
+        let center = left + width / 2.0;
+
+        if !title.is_empty() {
+            self.centered_text(font_size * TITLE_SCALE, center, y, title);
+        }
+
+        let grid_top = y + title_height;
+        for (i, idx) in size.cells().into_iter().enumerate() {
+            let cell_x = x + (i % n) as f32 * cell;
+            let cell_y = grid_top + (i / n) as f32 * cell;
+            self.rect(cell_x, cell_y, cell, cell);
+
+            // Shrink long goals until they fit into the cell, like the PNG export does.
+            let max_width = cell - PADDING * 2.0;
+            let mut text_size = font_size;
+            let mut lines = wrap_text(&board[idx], max_width, |line| text_width(text_size, line));
+            while lines.len() as f32 * text_size * 1.2 > max_width && text_size > MIN_FONT_SIZE {
+                text_size -= 0.5;
+                lines = wrap_text(&board[idx], max_width, |line| text_width(text_size, line));
+            }
+
+            let line_height = text_size * 1.2;
+            let mut line_y = cell_y + (cell - lines.len() as f32 * line_height) / 2.0;
+            for line in lines {
+                self.centered_text(text_size, cell_x + cell / 2.0, line_y, &line);
+                line_y += line_height;
+            }
+        }
+
+        self.centered_text(
+            options.font_size * 0.8,
+            center,
+            grid_top + grid + options.font_size * 0.5,
+            caption,
+        );
+    }
+}
+
+/// Splits the printable part of the page into `count` equal areas.
+fn page_areas(count: usize) -> Vec<(f32, f32, f32, f32)> {
+    let width = PAGE_WIDTH - MARGIN * 2.0;
+    let height = PAGE_HEIGHT - MARGIN * 2.0;
+
+    match count {
+        2 => (0..2)
+            .map(|i| {
+                (
+                    MARGIN,
+                    MARGIN + i as f32 * height / 2.0,
+                    width,
+                    height / 2.0,
+                )
+            })
+            .collect(),
+        4 => (0..4)
+            .map(|i| {
+                (
+                    MARGIN + (i % 2) as f32 * width / 2.0,
+                    MARGIN + (i / 2) as f32 * height / 2.0,
+                    width / 2.0,
+                    height / 2.0,
+                )
+            })
+            .collect(),
+        _ => vec![(MARGIN, MARGIN, width, height)],
+    }
+}
+
+/// Writes every board into the PDF, each one gets a caption with its number and the seed.
+pub(crate) fn render_pdf(
+    boards: &[[String; BOARD_CELLS]],
+    size: FieldSize,
+    options: &PdfOptions,
+    title: &str,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let areas = page_areas(options.per_page);
+    let mut pages = vec![];
+
+    for (page_idx, chunk) in boards.chunks(areas.len()).enumerate() {
+        let mut page = Page::default();
+        page.content.extend(b"0.75 w\n");
+
+        for (i, (board, area)) in chunk.iter().zip(&areas).enumerate() {
+            let number = page_idx * areas.len() + i + 1;
+            let caption = if options.current_board {
+                format!("Board {}", number)
+            } else {
+                format!("Board {} - Seed {}", number, options.seed)
+            };
+            page.board(board, size, options, title, &caption, *area);
+        }
+
+        pages.push(page);
+    }
+
+    std::fs::write(path, write_document(&pages))?;
+
+    Ok(())
+}
+
+fn write_document(pages: &[Page]) -> Vec<u8> {
+    // Objects 1 to 3 are fixed, then each page takes two: the page and its content.
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", 4 + i * 2))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                5 + i * 2
+            )
+            .into_bytes(),
+        );
+
+        let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+        stream.extend(&page.content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+
+    let xref = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+
+    out
+}
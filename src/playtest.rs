@@ -0,0 +1,211 @@
+use std::time::{Duration, Instant};
+
+use egui::Color32;
+
+use crate::board::{FieldSize, BOARD_CELLS};
+
+/// Team colors as they are on BingoSync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Team {
+    Orange,
+    Red,
+    Blue,
+    Green,
+    Purple,
+    Navy,
+    Teal,
+    Brown,
+    Pink,
+    Yellow,
+}
+
+impl Team {
+    pub(crate) const ALL: [Team; 10] = [
+        Team::Orange,
+        Team::Red,
+        Team::Blue,
+        Team::Green,
+        Team::Purple,
+        Team::Navy,
+        Team::Teal,
+        Team::Brown,
+        Team::Pink,
+        Team::Yellow,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Team::Orange => "Orange",
+            Team::Red => "Red",
+            Team::Blue => "Blue",
+            Team::Green => "Green",
+            Team::Purple => "Purple",
+            Team::Navy => "Navy",
+            Team::Teal => "Teal",
+            Team::Brown => "Brown",
+            Team::Pink => "Pink",
+            Team::Yellow => "Yellow",
+        }
+    }
+
+    /// Accepts the lowercase names BingoSync uses in its API.
+    #[cfg(feature = "bingosync")]
+    pub(crate) fn from_name(name: &str) -> Option<Team> {
+        Team::ALL
+            .into_iter()
+            .find(|team| team.name().eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn color(self) -> Color32 {
+        match self {
+            Team::Orange => Color32::from_rgb(0xff, 0x9c, 0x12),
+            Team::Red => Color32::from_rgb(0xff, 0x49, 0x44),
+            Team::Blue => Color32::from_rgb(0x40, 0x9c, 0xff),
+            Team::Green => Color32::from_rgb(0x31, 0xd8, 0x14),
+            Team::Purple => Color32::from_rgb(0x82, 0x2d, 0xbf),
+            Team::Navy => Color32::from_rgb(0x0d, 0x48, 0xb5),
+            Team::Teal => Color32::from_rgb(0x41, 0x96, 0x95),
+            Team::Brown => Color32::from_rgb(0xab, 0x5c, 0x23),
+            Team::Pink => Color32::from_rgb(0xed, 0x86, 0xaa),
+            Team::Yellow => Color32::from_rgb(0xd8, 0xd0, 0x14),
+        }
+    }
+}
+
+pub(crate) struct PlayEvent {
+    pub(crate) at: Duration,
+    pub(crate) team: Team,
+    pub(crate) text: String,
+}
+
+/// Local dry run of a board, marks are kept per team like non-lockout rooms.
+pub(crate) struct Playtest {
+    pub(crate) team: Team,
+    pub(crate) marks: [Vec<Team>; BOARD_CELLS],
+    pub(crate) events: Vec<PlayEvent>,
+    /// 5x5 board of a watched room, shown in place of the local board.
+    /// Its marks are mirrored from the room, so the grid doesn't take clicks.
+    pub(crate) room_board: Option<[String; BOARD_CELLS]>,
+    started: Instant,
+}
+
+impl Default for Playtest {
+    fn default() -> Self {
+        Self {
+            team: Team::Red,
+            marks: Default::default(),
+            events: vec![],
+            room_board: None,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Playtest {
+    /// Clears all marks and restarts the timer, keeping the selected team.
+    pub(crate) fn reset(&mut self) {
+        *self = Self {
+            team: self.team,
+            room_board: self.room_board.take(),
+            ..Default::default()
+        };
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub(crate) fn is_marked(&self, idx: usize, team: Team, free_cell: Option<usize>) -> bool {
+        Some(idx) == free_cell || self.marks[idx].contains(&team)
+    }
+
+    /// Names of the lines fully marked by the team.
+    pub(crate) fn bingos(
+        &self,
+        team: Team,
+        size: FieldSize,
+        free_cell: Option<usize>,
+    ) -> Vec<String> {
+        size.lines()
+            .into_iter()
+            .filter(|(_, line)| line.iter().all(|idx| self.is_marked(*idx, team, free_cell)))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    pub(crate) fn blackout(&self, team: Team, size: FieldSize, free_cell: Option<usize>) -> bool {
+        size.cells()
+            .into_iter()
+            .all(|idx| self.is_marked(idx, team, free_cell))
+    }
+
+    /// Marks or unmarks the cell for the current team and logs what it changed.
+    pub(crate) fn toggle(
+        &mut self,
+        idx: usize,
+        text: &str,
+        size: FieldSize,
+        free_cell: Option<usize>,
+    ) {
+        self.set_mark(
+            self.team,
+            idx,
+            text,
+            size,
+            free_cell,
+            !self.marks[idx].contains(&self.team),
+        );
+    }
+
+    pub(crate) fn set_mark(
+        &mut self,
+        team: Team,
+        idx: usize,
+        text: &str,
+        size: FieldSize,
+        free_cell: Option<usize>,
+        marked: bool,
+    ) {
+        if marked == self.marks[idx].contains(&team) {
+            return;
+        }
+
+        let bingos = self.bingos(team, size, free_cell);
+        let at = self.elapsed();
+
+        if marked {
+            self.marks[idx].push(team);
+        } else {
+            self.marks[idx].retain(|item| *item != team);
+        }
+
+        self.events.push(PlayEvent {
+            at,
+            team,
+            text: format!("{} \"{}\"", if marked { "marked" } else { "cleared" }, text),
+        });
+
+        for line in self.bingos(team, size, free_cell) {
+            if !bingos.contains(&line) {
+                self.events.push(PlayEvent {
+                    at,
+                    team,
+                    text: format!("got bingo on {}", line),
+                });
+            }
+        }
+
+        if marked && self.blackout(team, size, free_cell) {
+            self.events.push(PlayEvent {
+                at,
+                team,
+                text: String::from("got blackout"),
+            });
+        }
+    }
+}
+
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
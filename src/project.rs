@@ -0,0 +1,72 @@
+//! Project files: a database with the options and boards used to run it, in one JSON file.
+
+use std::{fs, path::Path};
+
+use anyhow::bail;
+
+use crate::{board::FieldSize, database::NamedDatabase, export::ExportOptions, pdf::PdfOptions};
+
+/// Version of the project layout, bump it when the layout changes.
+pub(crate) const PROJECT_VERSION: u32 = 1;
+
+pub(crate) const EXTENSION: &str = "bingoproj";
+
+/// Board kept next to the one being edited.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct SavedBoard {
+    pub(crate) name: String,
+    pub(crate) field_size: FieldSize,
+    pub(crate) cells: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct Project {
+    pub(crate) database: NamedDatabase,
+    pub(crate) category: String,
+    /// Board being edited.
+    pub(crate) board: Vec<String>,
+    #[serde(default)]
+    pub(crate) boards: Vec<SavedBoard>,
+    #[serde(default)]
+    pub(crate) export_options: ExportOptions,
+    /// Also keeps the seed and weighted mode boards are generated with.
+    #[serde(default)]
+    pub(crate) pdf_options: PdfOptions,
+    /// Weighted mode of the `Analyze` window.
+    #[serde(default)]
+    pub(crate) analysis_weighted: bool,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct ProjectFile {
+    version: u32,
+    #[serde(flatten)]
+    project: Project,
+}
+
+impl Project {
+    pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = serde_json::from_str::<ProjectFile>(&fs::read_to_string(path)?)?;
+
+        if file.version > PROJECT_VERSION {
+            bail!(
+                "Project version {} is newer than supported {}",
+                file.version,
+                PROJECT_VERSION
+            );
+        }
+
+        Ok(file.project)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let file = ProjectFile {
+            version: PROJECT_VERSION,
+            project: self.to_owned(),
+        };
+
+        fs::write(path, serde_json::to_string_pretty(&file)?)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+//! Software rendering of the board into a PNG, so no GPU is needed.
+
+use std::{fs::File, io::BufWriter, path::Path};
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use anyhow::anyhow;
+
+use crate::{
+    board::{FieldSize, BOARD_CELLS},
+    export::ExportOptions,
+};
+
+struct Theme {
+    background: [u8; 3],
+    cell: [u8; 3],
+    line: [u8; 3],
+    text: [u8; 3],
+}
+
+const LIGHT: Theme = Theme {
+    background: [0xff, 0xff, 0xff],
+    cell: [0xf2, 0xf2, 0xf2],
+    line: [0x60, 0x60, 0x60],
+    text: [0x10, 0x10, 0x10],
+};
+
+const DARK: Theme = Theme {
+    background: [0x1b, 0x1b, 0x1b],
+    cell: [0x00, 0x00, 0x00],
+    line: [0x80, 0x80, 0x80],
+    text: [0xf0, 0xf0, 0xf0],
+};
+
+const CELL_SIZE: u32 = 160;
+const MARGIN: u32 = 16;
+const PADDING: f32 = 8.0;
+const LINE_WIDTH: u32 = 2;
+const TITLE_SIZE: f32 = 32.0;
+const FOOTER_SIZE: f32 = 18.0;
+const MAX_TEXT_SIZE: f32 = 22.0;
+const MIN_TEXT_SIZE: f32 = 10.0;
+
+/// Breaks text into lines no wider than `max_width`, words that don't fit are split.
+pub(crate) fn wrap_text(text: &str, max_width: f32, measure: impl Fn(&str) -> f32) -> Vec<String> {
+    let mut lines = vec![];
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_owned()
+            } else {
+                format!("{} {}", line, word)
+            };
+
+            if measure(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            for ch in word.chars() {
+                line.push(ch);
+                if measure(&line) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::take(&mut line));
+                    line.push(ch);
+                }
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, color: [u8; 3]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: [color[0], color[1], color[2], 0xff].repeat((width * height) as usize),
+        }
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: [u8; 3], alpha: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let idx = ((y * self.width + x) * 4) as usize;
+        for (channel, value) in color.iter().enumerate() {
+            let old = self.pixels[idx + channel] as f32;
+            self.pixels[idx + channel] =
+                (old + (*value as f32 - old) * alpha.clamp(0.0, 1.0)) as u8;
+        }
+    }
+
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 3]) {
+        for py in y..y + height {
+            for px in x..x + width {
+                self.blend(px, py, color, 1.0);
+            }
+        }
+    }
+
+    /// Draws a single line of text with its top left corner at `x`, `y`.
+    fn text(&mut self, font: &FontRef<'_>, size: f32, x: f32, y: f32, text: &str, color: [u8; 3]) {
+        let scaled = font.as_scaled(PxScale::from(size));
+        let mut caret = x;
+        let mut previous = None;
+
+        for ch in text.chars() {
+            let id = scaled.glyph_id(ch);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, id);
+            }
+            previous = Some(id);
+
+            let glyph =
+                id.with_scale_and_position(size, ab_glyph::point(caret, y + scaled.ascent()));
+            caret += scaled.h_advance(id);
+
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let px = bounds.min.x as i32 + gx as i32;
+                    let py = bounds.min.y as i32 + gy as i32;
+                    if px >= 0 && py >= 0 {
+                        self.blend(px as u32, py as u32, color, coverage);
+                    }
+                });
+            }
+        }
+    }
+}
+
+fn text_width(font: &FontRef<'_>, size: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    text.chars()
+        .map(|ch| scaled.h_advance(scaled.glyph_id(ch)))
+        .sum()
+}
+
+pub(crate) fn render_png(
+    board: &[String; BOARD_CELLS],
+    size: FieldSize,
+    options: &ExportOptions,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let fonts = egui::FontDefinitions::default();
+    let font_data = fonts
+        .font_data
+        .get("Ubuntu-Light")
+        .ok_or_else(|| anyhow!("Default font is missing"))?;
+    let font = FontRef::try_from_slice(&font_data.font)?;
+    let theme = if options.dark { &DARK } else { &LIGHT };
+
+    let n = size as u32;
+    let grid = n * CELL_SIZE + LINE_WIDTH;
+    let title_height = if options.title.is_empty() {
+        0
+    } else {
+        TITLE_SIZE as u32 + MARGIN
+    };
+    let footer_height = if options.footer.is_empty() {
+        0
+    } else {
+        FOOTER_SIZE as u32 + MARGIN
+    };
+    let width = grid + MARGIN * 2;
+    let height = grid + MARGIN * 2 + title_height + footer_height;
+
+    let mut canvas = Canvas::new(width, height, theme.background);
+
+    if !options.title.is_empty() {
+        let x = (width as f32 - text_width(&font, TITLE_SIZE, &options.title)) / 2.0;
+        canvas.text(
+            &font,
+            TITLE_SIZE,
+            x.max(0.0),
+            MARGIN as f32,
+            &options.title,
+            theme.text,
+        );
+    }
+
+    let top = MARGIN + title_height;
+    canvas.fill(MARGIN, top, grid, grid, theme.line);
+
+    for (i, idx) in size.cells().into_iter().enumerate() {
+        let x = MARGIN + (i as u32 % n) * CELL_SIZE + LINE_WIDTH;
+        let y = top + (i as u32 / n) * CELL_SIZE + LINE_WIDTH;
+        let inner = CELL_SIZE - LINE_WIDTH;
+
+        canvas.fill(x, y, inner, inner, theme.cell);
+
+        // Shrink long goals until they fit into the cell, which is square.
+        let max_width = inner as f32 - PADDING * 2.0;
+        let max_height = max_width;
+        let mut text_size = MAX_TEXT_SIZE;
+        let mut lines = wrap_text(&board[idx], max_width, |line| {
+            text_width(&font, text_size, line)
+        });
+        while lines.len() as f32 * text_size * 1.2 > max_height && text_size > MIN_TEXT_SIZE {
+            text_size -= 1.0;
+            lines = wrap_text(&board[idx], max_width, |line| {
+                text_width(&font, text_size, line)
+            });
+        }
+
+        let line_height = text_size * 1.2;
+        let mut line_y = y as f32 + (inner as f32 - lines.len() as f32 * line_height) / 2.0;
+        for line in lines {
+            let line_x = x as f32 + (inner as f32 - text_width(&font, text_size, &line)) / 2.0;
+            canvas.text(&font, text_size, line_x, line_y, &line, theme.text);
+            line_y += line_height;
+        }
+    }
+
+    if !options.footer.is_empty() {
+        let x = (width as f32 - text_width(&font, FOOTER_SIZE, &options.footer)) / 2.0;
+        let y = (top + grid + MARGIN) as f32;
+        canvas.text(
+            &font,
+            FOOTER_SIZE,
+            x.max(0.0),
+            y,
+            &options.footer,
+            theme.text,
+        );
+    }
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&canvas.pixels)?;
+
+    Ok(())
+}
@@ -0,0 +1,386 @@
+//! Search queries of the Database tab.
+//!
+//! Words match category or text, `field:value` limits them to a field, `-` in front negates
+//! a term and `/.../` makes a regex. For example `category:Combat weight>5 enabled:false`.
+
+use std::borrow::Cow;
+
+use anyhow::{anyhow, bail};
+use regex::{Regex, RegexBuilder};
+
+use crate::card::CardRow;
+
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct SearchSettings {
+    pub(crate) filter: String,
+    pub(crate) case_sensitive: bool,
+    /// Every word is a regex, not only the ones in slashes.
+    pub(crate) regex: bool,
+    /// Show only enabled or disabled cards.
+    pub(crate) enabled: Option<bool>,
+}
+
+pub(crate) const SYNTAX_HELP: &str = "Words match category or text\n\
+    category:, text: - search a single field, = for an exact match\n\
+    weight>5, weight<=2, weight=1 - compare weights\n\
+    enabled:false - enabled flag\n\
+    /regex/ - regular expression\n\
+    -word - exclude matches\n\
+    \"two words\" - quoted phrase";
+
+enum Field {
+    Any,
+    Category,
+    Text,
+}
+
+enum Pattern {
+    Contains(String),
+    Exact(String),
+    Regex(Regex),
+}
+
+enum Compare {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+enum Condition {
+    Match(Field, Pattern),
+    Weight(Compare, f64),
+    Enabled(bool),
+}
+
+struct Term {
+    negate: bool,
+    condition: Condition,
+}
+
+/// Parsed search, a card has to match every term.
+pub(crate) struct Query {
+    terms: Vec<Term>,
+    case_sensitive: bool,
+}
+
+impl Query {
+    pub(crate) fn parse(settings: &SearchSettings) -> anyhow::Result<Self> {
+        let mut terms = tokenize(&settings.filter)?
+            .into_iter()
+            .map(|token| parse_term(&token, settings))
+            .collect::<anyhow::Result<Vec<Term>>>()?;
+
+        if let Some(enabled) = settings.enabled {
+            terms.push(Term {
+                negate: false,
+                condition: Condition::Enabled(enabled),
+            });
+        }
+
+        Ok(Self {
+            terms,
+            case_sensitive: settings.case_sensitive,
+        })
+    }
+
+    pub(crate) fn matches(&self, card: &CardRow) -> bool {
+        self.terms
+            .iter()
+            .all(|term| self.matches_term(term, card) != term.negate)
+    }
+
+    fn matches_term(&self, term: &Term, card: &CardRow) -> bool {
+        match &term.condition {
+            Condition::Match(Field::Any, pattern) => {
+                self.matches_text(pattern, &card.0) || self.matches_text(pattern, &card.1)
+            }
+            Condition::Match(Field::Category, pattern) => self.matches_text(pattern, &card.0),
+            Condition::Match(Field::Text, pattern) => self.matches_text(pattern, &card.1),
+            Condition::Weight(compare, value) => match compare {
+                Compare::Less => card.2 < *value,
+                Compare::LessOrEqual => card.2 <= *value,
+                Compare::Equal => card.2 == *value,
+                Compare::GreaterOrEqual => card.2 >= *value,
+                Compare::Greater => card.2 > *value,
+            },
+            Condition::Enabled(enabled) => card.3 == *enabled,
+        }
+    }
+
+    fn matches_text(&self, pattern: &Pattern, text: &str) -> bool {
+        // Case is handled by the regex itself, the other patterns are lowercased already.
+        if let Pattern::Regex(regex) = pattern {
+            return regex.is_match(text);
+        }
+
+        let text = if self.case_sensitive {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(text.to_lowercase())
+        };
+
+        match pattern {
+            Pattern::Contains(value) => text.contains(value.as_str()),
+            Pattern::Exact(value) => *text == *value,
+            Pattern::Regex(_) => unreachable!(),
+        }
+    }
+}
+
+/// Splits on whitespace, double quotes keep a phrase together.
+fn tokenize(filter: &str) -> anyhow::Result<Vec<String>> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut quoted = false;
+
+    for ch in filter.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            ch if ch.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            ch => token.push(ch),
+        }
+    }
+    if quoted {
+        bail!("Unterminated quote");
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_term(token: &str, settings: &SearchSettings) -> anyhow::Result<Term> {
+    let (negate, token) = match token.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+
+    let name_len = token
+        .find(|ch: char| !ch.is_ascii_alphabetic())
+        .unwrap_or(token.len());
+    let (name, rest) = token.split_at(name_len);
+
+    let operator = [">=", "<=", ":", "=", ">", "<"]
+        .into_iter()
+        .find(|operator| rest.starts_with(operator));
+
+    let condition = match (name.to_ascii_lowercase().as_str(), operator) {
+        ("category" | "cat", Some(operator)) => Condition::Match(
+            Field::Category,
+            pattern(&rest[operator.len()..], operator == "=", settings)?,
+        ),
+        ("text", Some(operator)) => Condition::Match(
+            Field::Text,
+            pattern(&rest[operator.len()..], operator == "=", settings)?,
+        ),
+        ("weight" | "w", Some(operator)) => {
+            let value = rest[operator.len()..].trim();
+            let value = value
+                .parse::<f64>()
+                .map_err(|_| anyhow!("Invalid weight: {}", value))?;
+            let compare = match operator {
+                "<" => Compare::Less,
+                "<=" => Compare::LessOrEqual,
+                ">=" => Compare::GreaterOrEqual,
+                ">" => Compare::Greater,
+                _ => Compare::Equal,
+            };
+
+            Condition::Weight(compare, value)
+        }
+        ("enabled", Some(":" | "=")) => {
+            let value = &rest[1..];
+            let enabled = match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" | "on" => true,
+                "false" | "no" | "n" | "0" | "off" => false,
+                _ => bail!("Invalid enabled value: {}", value),
+            };
+
+            Condition::Enabled(enabled)
+        }
+        _ => Condition::Match(Field::Any, pattern(token, false, settings)?),
+    };
+
+    Ok(Term { negate, condition })
+}
+
+fn pattern(value: &str, exact: bool, settings: &SearchSettings) -> anyhow::Result<Pattern> {
+    let slashed = value
+        .strip_prefix('/')
+        .and_then(|value| value.strip_suffix('/'))
+        .filter(|value| !value.is_empty());
+
+    if let Some(value) = slashed.or(settings.regex.then_some(value)) {
+        let value = if exact {
+            format!("^(?:{})$", value)
+        } else {
+            value.to_owned()
+        };
+        let regex = RegexBuilder::new(&value)
+            .case_insensitive(!settings.case_sensitive)
+            .build()?;
+
+        return Ok(Pattern::Regex(regex));
+    }
+
+    let value = if settings.case_sensitive {
+        value.to_owned()
+    } else {
+        value.to_lowercase()
+    };
+
+    Ok(if exact {
+        Pattern::Exact(value)
+    } else {
+        Pattern::Contains(value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards() -> Vec<CardRow> {
+        vec![
+            CardRow("Combat".into(), "Win a fight".into(), 1.0, true),
+            CardRow("Combat".into(), "Win two fights".into(), 5.0, false),
+            CardRow("Items".into(), "Find a key".into(), 2.5, true),
+            CardRow("Items".into(), "Fight Club".into(), 10.0, true),
+        ]
+    }
+
+    fn search(settings: SearchSettings) -> Vec<String> {
+        let query = Query::parse(&settings).unwrap();
+
+        cards()
+            .into_iter()
+            .filter(|card| query.matches(card))
+            .map(|card| card.1)
+            .collect()
+    }
+
+    fn find(filter: &str) -> Vec<String> {
+        search(SearchSettings {
+            filter: filter.into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn words_match_category_or_text() {
+        assert_eq!(find(""), find("   "));
+        assert_eq!(find("").len(), 4);
+        assert_eq!(
+            find("fight"),
+            ["Win a fight", "Win two fights", "Fight Club"]
+        );
+        assert_eq!(find("items"), ["Find a key", "Fight Club"]);
+        assert_eq!(find("win fights"), ["Win two fights"]);
+    }
+
+    #[test]
+    fn fields_limit_the_match() {
+        assert_eq!(find("category:item"), ["Find a key", "Fight Club"]);
+        assert_eq!(find("cat:item"), ["Find a key", "Fight Club"]);
+        assert!(find("category=item").is_empty());
+        assert_eq!(find("Category=items"), ["Find a key", "Fight Club"]);
+        assert!(find("text:combat").is_empty());
+        assert_eq!(find("text=\"win a fight\""), ["Win a fight"]);
+    }
+
+    #[test]
+    fn negation_excludes_matches() {
+        assert_eq!(find("-combat"), ["Find a key", "Fight Club"]);
+        assert_eq!(
+            find("fight -category:items"),
+            ["Win a fight", "Win two fights"]
+        );
+        // A lone dash is a word.
+        assert!(find("-").is_empty());
+    }
+
+    #[test]
+    fn quotes_keep_phrases() {
+        assert_eq!(find("\"a fight\""), ["Win a fight"]);
+        assert_eq!(
+            find("-\"win a\""),
+            ["Win two fights", "Find a key", "Fight Club"]
+        );
+    }
+
+    #[test]
+    fn weights_compare() {
+        assert_eq!(find("weight>5"), ["Fight Club"]);
+        assert_eq!(find("weight>=5"), ["Win two fights", "Fight Club"]);
+        assert_eq!(find("weight<2.5"), ["Win a fight"]);
+        assert_eq!(find("weight<=2.5"), ["Win a fight", "Find a key"]);
+        assert_eq!(find("w=10"), ["Fight Club"]);
+        assert_eq!(find("weight:1"), ["Win a fight"]);
+        assert_eq!(find("-weight>1"), ["Win a fight"]);
+    }
+
+    #[test]
+    fn enabled_flag() {
+        assert_eq!(find("enabled:false"), ["Win two fights"]);
+        assert_eq!(find("enabled=yes").len(), 3);
+        assert_eq!(
+            search(SearchSettings {
+                enabled: Some(false),
+                ..Default::default()
+            }),
+            ["Win two fights"]
+        );
+    }
+
+    #[test]
+    fn regexes_and_case() {
+        assert_eq!(find("/^win/"), ["Win a fight", "Win two fights"]);
+        assert_eq!(find("text:/club$/"), ["Fight Club"]);
+        // `=` anchors the regex to the whole field.
+        assert!(find("text=/win/").is_empty());
+        assert_eq!(find("text=/win.*s/"), ["Win two fights"]);
+        assert_eq!(
+            search(SearchSettings {
+                filter: "fight$".into(),
+                regex: true,
+                ..Default::default()
+            }),
+            ["Win a fight"]
+        );
+        assert_eq!(
+            search(SearchSettings {
+                filter: "Fight".into(),
+                case_sensitive: true,
+                ..Default::default()
+            }),
+            ["Fight Club"]
+        );
+    }
+
+    #[test]
+    fn malformed_queries_fail() {
+        for filter in [
+            "\"two words",
+            "weight>heavy",
+            "weight>",
+            "enabled:maybe",
+            "/[/",
+            "category:/(/",
+        ] {
+            let settings = SearchSettings {
+                filter: filter.into(),
+                ..Default::default()
+            };
+
+            assert!(Query::parse(&settings).is_err(), "{}", filter);
+        }
+    }
+}
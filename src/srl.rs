@@ -0,0 +1,245 @@
+//! Goal lists in the format of SRL `generator.js` files:
+//! `bingoList[tier] = [{ name: "...", types: ["..."] }, ...];`
+
+use anyhow::{bail, Context};
+use itertools::Itertools;
+
+use crate::card::CardRow;
+
+/// SRL v5 generator difficulty tiers.
+pub(crate) const TIERS: usize = 25;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct SrlGoal {
+    name: String,
+    #[serde(default)]
+    types: Vec<String>,
+}
+
+/// Splits enabled cards into tiers, weights are scaled linearly from the lowest to the highest.
+fn tiers(cards: &[&CardRow]) -> Vec<Vec<SrlGoal>> {
+    let enabled = cards.iter().filter(|card| card.3).collect::<Vec<_>>();
+    let min = enabled.iter().map(|card| card.2).fold(f64::MAX, f64::min);
+    let max = enabled.iter().map(|card| card.2).fold(f64::MIN, f64::max);
+
+    let mut tiers: Vec<Vec<SrlGoal>> = (0..TIERS).map(|_| vec![]).collect();
+
+    for card in enabled {
+        let tier = if max > min {
+            ((card.2 - min) / (max - min) * (TIERS - 1) as f64).round() as usize
+        } else {
+            0
+        };
+
+        tiers[tier].push(SrlGoal {
+            name: card.1.to_owned(),
+            types: if card.0.is_empty() {
+                vec![]
+            } else {
+                vec![card.0.to_owned()]
+            },
+        });
+    }
+
+    tiers
+}
+
+pub(crate) fn to_js(cards: &[&CardRow]) -> anyhow::Result<String> {
+    let mut out = String::from("bingoList = [];\n");
+
+    for (idx, goals) in tiers(cards).iter().enumerate() {
+        out.push_str(&format!("bingoList[{}] = [\n", idx + 1));
+        for goal in goals {
+            out.push_str(&format!("  {},\n", serde_json::to_string(goal)?));
+        }
+        out.push_str("];\n");
+    }
+
+    Ok(out)
+}
+
+/// Same list as JSON, the array index is the tier so the first element is always empty.
+pub(crate) fn to_json(cards: &[&CardRow]) -> anyhow::Result<String> {
+    let mut tiers = tiers(cards);
+    tiers.insert(0, vec![]);
+
+    Ok(serde_json::to_string_pretty(&tiers)?)
+}
+
+/// Reads either a `generator.js` file or its JSON form back into cards.
+///
+/// The first type becomes the category and the tier becomes the weight.
+pub(crate) fn parse(source: &str) -> anyhow::Result<Vec<CardRow>> {
+    let tiers: Vec<(usize, Vec<SrlGoal>)> = if source.trim_start().starts_with('[') {
+        json5::from_str::<Vec<Vec<SrlGoal>>>(source)?
+            .into_iter()
+            .enumerate()
+            .collect()
+    } else {
+        parse_js(source)?
+    };
+
+    Ok(tiers
+        .into_iter()
+        .flat_map(|(tier, goals)| {
+            goals.into_iter().map(move |goal| {
+                CardRow(
+                    goal.types.into_iter().next().unwrap_or_default(),
+                    goal.name,
+                    tier as f64,
+                    true,
+                )
+            })
+        })
+        .collect())
+}
+
+fn parse_js(source: &str) -> anyhow::Result<Vec<(usize, Vec<SrlGoal>)>> {
+    let mut tiers = vec![];
+    let mut rest = source;
+
+    while let Some(start) = rest.find("bingoList[") {
+        rest = &rest[start + "bingoList[".len()..];
+
+        let Some((tier, tail)) = rest.split_once(']') else {
+            break;
+        };
+        // `bingoList = [];` and friends have no tier number.
+        let Ok(tier) = tier.trim().parse::<usize>() else {
+            continue;
+        };
+        let Some(list_start) = tail.find('[') else {
+            break;
+        };
+
+        let list = &tail[list_start..];
+        let len = list_len(list).with_context(|| format!("Unclosed list for tier {}", tier))?;
+        let goals = json5::from_str::<Vec<SrlGoal>>(&list[..len])
+            .with_context(|| format!("Invalid goals in tier {}", tier))?;
+
+        tiers.push((tier, goals));
+        rest = &list[len..];
+    }
+
+    if tiers.is_empty() {
+        bail!("No `bingoList[tier]` entries found");
+    }
+
+    Ok(tiers.into_iter().sorted_by_key(|(tier, _)| *tier).collect())
+}
+
+/// Length of the bracketed list at the start of `source`, skipping brackets in strings.
+fn list_len(source: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (idx, ch) in source.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match ch {
+            '"' | '\'' => quote = Some(ch),
+            '[' | '{' => depth += 1,
+            ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Written like the generator pages on SRL, with JS comments, quotes and trailing commas.
+    const GENERATOR_JS: &str = r#"
+var bingoList = [];
+bingoList[10] = [
+  { name: "Defeat Ganon", types: ["boss", "castle"] },
+];
+bingoList[1] = [
+  { name: "Get the Fairy Slingshot", types: ["item"] },
+  // Kokiri goals
+  { name: 'Talk to "Mido" [twice]', types: ['kokiri'], },
+  { name: "Free Space" },
+];
+$(function () { srl.bingo(bingoList, 5); });
+"#;
+
+    #[test]
+    fn generator_js_is_read() {
+        let cards = parse(GENERATOR_JS).unwrap();
+
+        assert_eq!(
+            cards,
+            [
+                CardRow("item".into(), "Get the Fairy Slingshot".into(), 1.0, true),
+                CardRow(
+                    "kokiri".into(),
+                    "Talk to \"Mido\" [twice]".into(),
+                    1.0,
+                    true
+                ),
+                CardRow("".into(), "Free Space".into(), 1.0, true),
+                CardRow("boss".into(), "Defeat Ganon".into(), 10.0, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_tiers_are_weights() {
+        let cards = parse(r#"[[], [{"name": "A", "types": ["x"]}], [], [{name: 'B'}]]"#).unwrap();
+
+        assert_eq!(
+            cards,
+            [
+                CardRow("x".into(), "A".into(), 1.0, true),
+                CardRow("".into(), "B".into(), 3.0, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn exports_read_back() {
+        let cards = [
+            CardRow("Combat".into(), "Win a fight".into(), 1.0, true),
+            CardRow("Items".into(), "Find a key".into(), 25.0, true),
+            CardRow("Items".into(), "Disabled".into(), 5.0, false),
+        ];
+        let pool = cards.iter().collect::<Vec<_>>();
+
+        // The lowest weight is tier 1 and the highest tier 25, disabled cards are left out.
+        for source in [to_js(&pool).unwrap(), to_json(&pool).unwrap()] {
+            assert_eq!(parse(&source).unwrap(), cards[..2]);
+        }
+    }
+
+    #[test]
+    fn other_input_fails() {
+        for source in [
+            "",
+            "{ \"name\": \"Goal\" }",
+            "[{\"name\": \"Goal\"}]",
+            "[[{\"types\": []}]]",
+            "bingoList[1] = [{ name: \"Goal\" }",
+            "bingoList[1] = { name: \"Goal\" };",
+        ] {
+            assert!(parse(source).is_err(), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Database statistics of the Statistics tab: category counts, weight histogram and pick chances.
+
+use std::collections::BTreeMap;
+
+use crate::{board::FieldSize, card::CardRow};
+
+/// Weight histogram buckets.
+const BUCKETS: usize = 10;
+
+#[derive(Default)]
+pub(crate) struct CategoryStats {
+    pub(crate) total: usize,
+    pub(crate) enabled: usize,
+    /// Enabled cards that can be picked in weighted mode.
+    pub(crate) weighted: usize,
+}
+
+pub(crate) struct WeightBucket {
+    pub(crate) min: f64,
+    pub(crate) max: f64,
+    pub(crate) count: usize,
+}
+
+pub(crate) struct CardChance {
+    pub(crate) category: String,
+    pub(crate) text: String,
+    pub(crate) weight: f64,
+    /// Chance to be the next pick.
+    pub(crate) draw: f64,
+    /// Chance to end up on the board.
+    pub(crate) board: f64,
+}
+
+/// Summary of a database, `all` adds up every category.
+pub(crate) struct DatabaseStats {
+    pub(crate) categories: BTreeMap<String, CategoryStats>,
+    pub(crate) all: CategoryStats,
+    pub(crate) histogram: Vec<WeightBucket>,
+    pub(crate) chances: Vec<CardChance>,
+}
+
+impl DatabaseStats {
+    /// Chances are for the weighted mode with the cards of `pool` and `cells` cards on a board.
+    pub(crate) fn new<'a>(
+        cards: impl Iterator<Item = &'a CardRow>,
+        pool: &[&CardRow],
+        cells: usize,
+    ) -> Self {
+        let mut categories: BTreeMap<String, CategoryStats> = BTreeMap::new();
+        let mut all = CategoryStats::default();
+        let mut weights = vec![];
+
+        for card in cards {
+            let category = categories.entry(card.0.to_owned()).or_default();
+
+            for stats in [&mut *category, &mut all] {
+                stats.total += 1;
+                if card.3 {
+                    stats.enabled += 1;
+                    if card.2 > 0.0 {
+                        stats.weighted += 1;
+                    }
+                }
+            }
+
+            if card.3 {
+                weights.push(card.2);
+            }
+        }
+
+        Self {
+            categories,
+            all,
+            histogram: histogram(&weights),
+            chances: chances(pool, cells),
+        }
+    }
+}
+
+impl CategoryStats {
+    /// Enough cards to fill `cells` in the plain and the weighted mode.
+    pub(crate) fn fits(&self, cells: usize) -> (bool, bool) {
+        (self.enabled >= cells, self.weighted >= cells)
+    }
+}
+
+/// Cards the generator has to pick for a field, without the free space.
+pub(crate) fn needed_cards(size: FieldSize, free_space: bool) -> usize {
+    size.cells().len() - usize::from(free_space && size.has_center())
+}
+
+fn histogram(weights: &[f64]) -> Vec<WeightBucket> {
+    if weights.is_empty() {
+        return vec![];
+    }
+
+    let min = weights.iter().copied().fold(f64::MAX, f64::min);
+    let max = weights.iter().copied().fold(f64::MIN, f64::max);
+
+    if max <= min {
+        return vec![WeightBucket {
+            min,
+            max,
+            count: weights.len(),
+        }];
+    }
+
+    let width = (max - min) / BUCKETS as f64;
+    let mut buckets = (0..BUCKETS)
+        .map(|idx| WeightBucket {
+            min: min + width * idx as f64,
+            max: min + width * (idx + 1) as f64,
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+
+    for weight in weights {
+        let idx = (((weight - min) / width) as usize).min(BUCKETS - 1);
+        buckets[idx].count += 1;
+    }
+
+    buckets
+}
+
+/// Board chances use the approximation for sampling without replacement:
+/// `1 - exp(-weight * t)`, with `t` picked so the chances add up to the number of cells.
+fn chances(pool: &[&CardRow], cells: usize) -> Vec<CardChance> {
+    let total: f64 = pool.iter().map(|card| card.2.max(0.0)).sum();
+    let pickable = pool.iter().filter(|card| card.2 > 0.0).count();
+
+    let board_chance = |weight: f64, t: f64| 1.0 - (-weight * t).exp();
+    let t = if pickable <= cells {
+        f64::INFINITY
+    } else {
+        let expected = |t: f64| {
+            pool.iter()
+                .map(|card| board_chance(card.2.max(0.0), t))
+                .sum::<f64>()
+        };
+
+        let mut low = 0.0;
+        let mut high = 1.0;
+        while expected(high) < cells as f64 {
+            high *= 2.0;
+        }
+        for _ in 0..60 {
+            let mid = (low + high) / 2.0;
+            if expected(mid) < cells as f64 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        high
+    };
+
+    let mut chances = pool
+        .iter()
+        .map(|card| CardChance {
+            category: card.0.to_owned(),
+            text: card.1.to_owned(),
+            weight: card.2,
+            draw: if total > 0.0 {
+                card.2.max(0.0) / total
+            } else {
+                0.0
+            },
+            board: if card.2 > 0.0 {
+                board_chance(card.2, t)
+            } else {
+                0.0
+            },
+        })
+        .collect::<Vec<_>>();
+
+    chances.sort_by(|a, b| b.draw.total_cmp(&a.draw));
+
+    chances
+}
@@ -0,0 +1,660 @@
+//! SQLite storage of the databases.
+//!
+//! Cards keep a row id and an order key, so a sync only writes the cards that were added,
+//! removed, edited or moved. The store remembers a hash of every card instead of a copy.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::{
+    card::CardRow,
+    database::{DatabaseSettings, NamedDatabase},
+};
+
+/// Bump it and add a step to `migrate` when the tables change.
+const SCHEMA_VERSION: i32 = 2;
+
+/// Gap between the order keys of neighbouring cards, cards moved in between take the middle.
+const KEY_SPACING: i64 = 1 << 16;
+
+/// Database as the app holds it, the cards are only borrowed for a sync.
+pub(crate) struct DatabaseView<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) settings: &'a DatabaseSettings,
+    pub(crate) cards: Vec<&'a CardRow>,
+}
+
+impl<'a> From<&'a NamedDatabase> for DatabaseView<'a> {
+    fn from(database: &'a NamedDatabase) -> Self {
+        Self {
+            name: &database.name,
+            settings: &database.settings,
+            cards: database.cards.iter().collect(),
+        }
+    }
+}
+
+/// Card as it is in the file, the hash stands in for its content.
+#[derive(Clone, Copy)]
+struct StoredCard {
+    id: i64,
+    key: i64,
+    hash: u64,
+}
+
+struct StoredDatabase {
+    id: i64,
+    name: String,
+    settings: String,
+    cards: Vec<StoredCard>,
+}
+
+pub(crate) struct CardStore {
+    conn: Connection,
+    /// Databases as they are in the file, in order.
+    written: Vec<StoredDatabase>,
+}
+
+impl CardStore {
+    pub(crate) fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)?;
+        migrate(&conn)?;
+
+        Ok(Self {
+            conn,
+            written: vec![],
+        })
+    }
+
+    /// Reads every database, empty for a new store. Later syncs write what differs from them.
+    pub(crate) fn load(&mut self) -> anyhow::Result<Vec<NamedDatabase>> {
+        let mut databases = vec![];
+        let mut written = vec![];
+        let mut select_databases = self
+            .conn
+            .prepare("SELECT id, name, settings FROM databases ORDER BY position")?;
+        let mut select_cards = self.conn.prepare(
+            "SELECT id, sort_key, category, text, weight, enabled FROM cards \
+             WHERE database = ?1 ORDER BY sort_key",
+        )?;
+
+        let rows = select_databases.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        for row in rows {
+            let (id, name, settings) = row?;
+            let mut cards = vec![];
+            let mut stored = vec![];
+
+            let rows = select_cards.query_map([id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    CardRow(row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?),
+                ))
+            })?;
+            for row in rows {
+                let (id, key, card) = row?;
+                stored.push(StoredCard {
+                    id,
+                    key,
+                    hash: card_hash(&card),
+                });
+                cards.push(card);
+            }
+
+            databases.push(NamedDatabase {
+                name: name.to_owned(),
+                cards,
+                settings: serde_json::from_str::<DatabaseSettings>(&settings).unwrap_or_default(),
+            });
+            written.push(StoredDatabase {
+                id,
+                name,
+                settings,
+                cards: stored,
+            });
+        }
+
+        self.written = written;
+
+        Ok(databases)
+    }
+
+    /// Writes the difference between `databases` and the file in a single transaction.
+    ///
+    /// Databases are matched by name, a database with a new name at the same position
+    /// is taken as renamed.
+    pub(crate) fn sync(&mut self, databases: &[DatabaseView]) -> anyhow::Result<()> {
+        let mut matches: Vec<Option<usize>> = vec![None; databases.len()];
+        for (position, database) in databases.iter().enumerate() {
+            matches[position] = (0..self.written.len()).find(|idx| {
+                self.written[*idx].name == database.name && !matches.contains(&Some(*idx))
+            });
+        }
+        for position in 0..databases.len().min(self.written.len()) {
+            if matches[position].is_none() && !matches.contains(&Some(position)) {
+                matches[position] = Some(position);
+            }
+        }
+
+        let tx = self.conn.transaction()?;
+        let mut synced = vec![];
+
+        for (position, (database, matched)) in databases.iter().zip(&matches).enumerate() {
+            let settings = serde_json::to_string(database.settings)?;
+            let old = matched.map(|idx| &self.written[idx]);
+
+            let id = match old {
+                Some(old) => {
+                    if *matched != Some(position)
+                        || old.name != database.name
+                        || old.settings != settings
+                    {
+                        tx.execute(
+                            "UPDATE databases SET position = ?2, name = ?3, settings = ?4 \
+                             WHERE id = ?1",
+                            params![old.id, position as i64, database.name, settings],
+                        )?;
+                    }
+                    old.id
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO databases (position, name, settings) VALUES (?1, ?2, ?3)",
+                        params![position as i64, database.name, settings],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            };
+
+            let old_cards = old.map_or(&[][..], |old| old.cards.as_slice());
+            let cards = sync_cards(&tx, id, old_cards, &database.cards)?;
+
+            synced.push((id, settings, cards));
+        }
+
+        for (idx, written) in self.written.iter().enumerate() {
+            if !matches.contains(&Some(idx)) {
+                tx.execute("DELETE FROM cards WHERE database = ?1", [written.id])?;
+                tx.execute("DELETE FROM databases WHERE id = ?1", [written.id])?;
+            }
+        }
+
+        tx.commit()?;
+
+        let mut written = std::mem::take(&mut self.written)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.written = synced
+            .into_iter()
+            .zip(databases.iter().zip(matches))
+            .map(
+                |((id, settings, cards), (database, matched))| StoredDatabase {
+                    id,
+                    name: database.name.to_owned(),
+                    settings,
+                    cards: match cards {
+                        Some(cards) => cards,
+                        None => matched
+                            .and_then(|idx| written[idx].take())
+                            .map(|old| old.cards)
+                            .unwrap_or_default(),
+                    },
+                },
+            )
+            .collect();
+
+        Ok(())
+    }
+}
+
+fn card_hash(card: &CardRow) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (&card.0, &card.1, card.2.to_bits(), card.3).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Writes the cards of a database that differ from `old`, `None` if nothing did.
+///
+/// Cards are matched by content first, the rest of the old cards are reused in order
+/// for edited ones. Only cards out of order with their neighbours get a new order key.
+fn sync_cards(
+    tx: &Transaction,
+    database: i64,
+    old: &[StoredCard],
+    cards: &[&CardRow],
+) -> anyhow::Result<Option<Vec<StoredCard>>> {
+    let hashes = cards.iter().map(|card| card_hash(card)).collect::<Vec<_>>();
+    if hashes.iter().eq(old.iter().map(|card| &card.hash)) {
+        return Ok(None);
+    }
+
+    let mut by_hash: HashMap<u64, VecDeque<usize>> = HashMap::new();
+    for (idx, card) in old.iter().enumerate() {
+        by_hash.entry(card.hash).or_default().push_back(idx);
+    }
+
+    let mut sources = hashes
+        .iter()
+        .map(|hash| by_hash.get_mut(hash).and_then(VecDeque::pop_front))
+        .collect::<Vec<_>>();
+    let mut used = vec![false; old.len()];
+    for idx in sources.iter().flatten() {
+        used[*idx] = true;
+    }
+
+    let mut unused = (0..old.len())
+        .filter(|idx| !used[*idx])
+        .collect::<VecDeque<_>>();
+    let mut edited = vec![false; cards.len()];
+    for (idx, source) in sources.iter_mut().enumerate() {
+        if source.is_none() {
+            *source = unused.pop_front();
+            edited[idx] = source.is_some();
+        }
+    }
+
+    let old_keys = sources
+        .iter()
+        .map(|source| source.map(|idx| old[idx].key))
+        .collect::<Vec<_>>();
+    let keys = order_keys(&old_keys, &keep_in_order(&old_keys)).unwrap_or_else(|| {
+        // No room left between two keys, everything is spaced out again.
+        (1..=cards.len() as i64)
+            .map(|idx| idx * KEY_SPACING)
+            .collect()
+    });
+
+    let mut insert = tx.prepare_cached(
+        "INSERT INTO cards (database, sort_key, category, text, weight, enabled) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let mut update = tx.prepare_cached(
+        "UPDATE cards SET sort_key = ?2, category = ?3, text = ?4, weight = ?5, enabled = ?6 \
+         WHERE id = ?1",
+    )?;
+    let mut update_key = tx.prepare_cached("UPDATE cards SET sort_key = ?2 WHERE id = ?1")?;
+    let mut delete = tx.prepare_cached("DELETE FROM cards WHERE id = ?1")?;
+
+    let mut stored = Vec::with_capacity(cards.len());
+
+    for (idx, card) in cards.iter().enumerate() {
+        let key = keys[idx];
+        let id = match sources[idx] {
+            Some(source) if edited[idx] => {
+                update.execute(params![old[source].id, key, card.0, card.1, card.2, card.3])?;
+                old[source].id
+            }
+            Some(source) => {
+                if old[source].key != key {
+                    update_key.execute(params![old[source].id, key])?;
+                }
+                old[source].id
+            }
+            None => {
+                insert.execute(params![database, key, card.0, card.1, card.2, card.3])?;
+                tx.last_insert_rowid()
+            }
+        };
+
+        stored.push(StoredCard {
+            id,
+            key,
+            hash: hashes[idx],
+        });
+    }
+
+    for idx in unused {
+        delete.execute([old[idx].id])?;
+    }
+
+    Ok(Some(stored))
+}
+
+/// Longest run of cards whose old keys are still in order, those keep their keys.
+fn keep_in_order(keys: &[Option<i64>]) -> Vec<bool> {
+    // `tails[len]` is the position that ends the best run of `len + 1` cards found so far.
+    let mut tails: Vec<usize> = vec![];
+    let mut previous: Vec<Option<usize>> = vec![None; keys.len()];
+
+    for (idx, key) in keys.iter().enumerate() {
+        let Some(key) = key else {
+            continue;
+        };
+
+        let len = tails.partition_point(|tail| keys[*tail].is_some_and(|tail| tail < *key));
+        previous[idx] = len.checked_sub(1).map(|len| tails[len]);
+        if len == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[len] = idx;
+        }
+    }
+
+    let mut keep = vec![false; keys.len()];
+    let mut next = tails.last().copied();
+    while let Some(idx) = next {
+        keep[idx] = true;
+        next = previous[idx];
+    }
+
+    keep
+}
+
+/// Order keys for every position, kept ones stay and the rest are spread between them.
+/// `None` when two kept keys are too close for the cards in between.
+fn order_keys(old_keys: &[Option<i64>], keep: &[bool]) -> Option<Vec<i64>> {
+    let mut keys = vec![0; old_keys.len()];
+    let mut start = 0;
+    let mut low = None;
+
+    for idx in 0..=old_keys.len() {
+        if idx < old_keys.len() && !keep[idx] {
+            continue;
+        }
+
+        let high = old_keys.get(idx).copied().flatten();
+        let run = idx - start;
+
+        for offset in 0..run {
+            let nth = offset as i64 + 1;
+            keys[start + offset] = match (low, high) {
+                (None, None) => nth * KEY_SPACING,
+                (None, Some(high)) => high - (run as i64 + 1 - nth) * KEY_SPACING,
+                (Some(low), None) => low + nth * KEY_SPACING,
+                (Some(low), Some(high)) => {
+                    let step = (high - low) / (run as i64 + 1);
+                    if step == 0 {
+                        return None;
+                    }
+                    low + nth * step
+                }
+            };
+        }
+
+        if let Some(high) = high {
+            keys[idx] = high;
+        }
+        low = high;
+        start = idx + 1;
+    }
+
+    Some(keys)
+}
+
+fn migrate(conn: &Connection) -> anyhow::Result<()> {
+    let version: i32 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .optional()?
+        .unwrap_or(0);
+
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "Card store version {} is newer than supported {}",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+
+    if version < 1 {
+        conn.execute_batch(
+            "BEGIN;
+             CREATE TABLE databases (
+                 position INTEGER PRIMARY KEY,
+                 name TEXT NOT NULL,
+                 settings TEXT NOT NULL
+             );
+             CREATE TABLE cards (
+                 database INTEGER NOT NULL,
+                 position INTEGER NOT NULL,
+                 category TEXT NOT NULL,
+                 text TEXT NOT NULL,
+                 weight REAL NOT NULL,
+                 enabled INTEGER NOT NULL,
+                 PRIMARY KEY (database, position)
+             );
+             CREATE INDEX cards_category ON cards (database, category);
+             CREATE INDEX cards_text ON cards (database, text);
+             PRAGMA user_version = 1;
+             COMMIT;",
+        )?;
+    }
+
+    // Row ids and order keys instead of positions, so inserts don't move every row.
+    if version < 2 {
+        conn.execute_batch(&format!(
+            "BEGIN;
+             ALTER TABLE databases RENAME TO databases_v1;
+             ALTER TABLE cards RENAME TO cards_v1;
+             DROP INDEX cards_category;
+             DROP INDEX cards_text;
+             CREATE TABLE databases (
+                 id INTEGER PRIMARY KEY,
+                 position INTEGER NOT NULL,
+                 name TEXT NOT NULL,
+                 settings TEXT NOT NULL
+             );
+             CREATE TABLE cards (
+                 id INTEGER PRIMARY KEY,
+                 database INTEGER NOT NULL REFERENCES databases (id),
+                 sort_key INTEGER NOT NULL,
+                 category TEXT NOT NULL,
+                 text TEXT NOT NULL,
+                 weight REAL NOT NULL,
+                 enabled INTEGER NOT NULL
+             );
+             CREATE INDEX cards_order ON cards (database, sort_key);
+             CREATE INDEX cards_category ON cards (database, category);
+             CREATE INDEX cards_text ON cards (database, text);
+             INSERT INTO databases (id, position, name, settings)
+                 SELECT position + 1, position, name, settings FROM databases_v1;
+             INSERT INTO cards (database, sort_key, category, text, weight, enabled)
+                 SELECT database + 1, (position + 1) * {}, category, text, weight, enabled
+                 FROM cards_v1;
+             DROP TABLE cards_v1;
+             DROP TABLE databases_v1;
+             PRAGMA user_version = 2;
+             COMMIT;",
+            KEY_SPACING
+        ))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(text: &str) -> CardRow {
+        CardRow(String::from("Combat"), text.to_owned(), 1.0, true)
+    }
+
+    fn cards(count: usize) -> Vec<CardRow> {
+        (1..=count)
+            .map(|idx| card(&format!("Goal {}", idx)))
+            .collect()
+    }
+
+    fn total_changes(store: &CardStore) -> i64 {
+        store
+            .conn
+            .query_row("SELECT total_changes()", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    /// Syncs a single database, returns the number of rows written.
+    fn sync(store: &mut CardStore, cards: &[CardRow]) -> i64 {
+        let before = total_changes(store);
+        let settings = DatabaseSettings::default();
+
+        store.sync(&[view("Default", &settings, cards)]).unwrap();
+
+        total_changes(store) - before
+    }
+
+    /// Row ids and texts in the stored order.
+    fn rows(store: &CardStore) -> Vec<(i64, String)> {
+        let mut select = store
+            .conn
+            .prepare("SELECT id, text FROM cards ORDER BY database, sort_key")
+            .unwrap();
+        let rows = select
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+
+        rows.collect::<Result<_, _>>().unwrap()
+    }
+
+    fn view<'a>(
+        name: &'a str,
+        settings: &'a DatabaseSettings,
+        cards: &'a [CardRow],
+    ) -> DatabaseView<'a> {
+        DatabaseView {
+            name,
+            settings,
+            cards: cards.iter().collect(),
+        }
+    }
+
+    fn reloaded(store: &mut CardStore) -> Vec<CardRow> {
+        store.load().unwrap().remove(0).cards
+    }
+
+    #[test]
+    fn sync_writes_only_changed_cards() {
+        let mut store = CardStore::open(Path::new(":memory:")).unwrap();
+        let mut cards = cards(5);
+
+        assert_eq!(sync(&mut store, &cards), 6);
+        assert_eq!(sync(&mut store, &cards), 0);
+        let initial = rows(&store);
+
+        cards.insert(0, card("Goal 0"));
+        assert_eq!(sync(&mut store, &cards), 1);
+        let inserted = rows(&store);
+        assert_eq!(inserted[0].1, "Goal 0");
+        assert_eq!(inserted[1..], initial[..]);
+
+        cards.remove(3);
+        assert_eq!(sync(&mut store, &cards), 1);
+
+        let last = cards.pop().unwrap();
+        cards.insert(0, last);
+        assert_eq!(sync(&mut store, &cards), 1);
+
+        let id = rows(&store)[2].0;
+        cards[2].2 = 5.0;
+        assert_eq!(sync(&mut store, &cards), 1);
+        assert_eq!(rows(&store)[2].0, id);
+
+        assert_eq!(reloaded(&mut store), cards);
+        assert_eq!(sync(&mut store, &cards), 0);
+    }
+
+    #[test]
+    fn sync_keeps_order() {
+        let mut store = CardStore::open(Path::new(":memory:")).unwrap();
+        let mut cards = cards(6);
+        cards.push(card("Goal 1"));
+        sync(&mut store, &cards);
+
+        cards.reverse();
+        sync(&mut store, &cards);
+        assert_eq!(reloaded(&mut store), cards);
+
+        // Keys between two cards run out after a while and are spread out again.
+        for idx in 0..40 {
+            cards.insert(1, card(&format!("Inserted {}", idx)));
+            assert!(sync(&mut store, &cards) >= 1);
+        }
+        assert_eq!(reloaded(&mut store), cards);
+
+        cards.retain(|card| card.1.starts_with("Goal"));
+        sync(&mut store, &cards);
+        assert_eq!(reloaded(&mut store), cards);
+    }
+
+    #[test]
+    fn sync_matches_databases_by_name() {
+        let mut store = CardStore::open(Path::new(":memory:")).unwrap();
+        let settings = DatabaseSettings::default();
+        let first = cards(3);
+        let second = cards(4);
+
+        store
+            .sync(&[
+                view("First", &settings, &first),
+                view("Second", &settings, &second),
+            ])
+            .unwrap();
+        let ids = rows(&store);
+
+        // The second database moves up, its cards stay as they are.
+        let before = total_changes(&store);
+        store.sync(&[view("Second", &settings, &second)]).unwrap();
+        assert_eq!(total_changes(&store) - before, 1 + 3 + 1);
+        assert_eq!(rows(&store), ids[3..]);
+
+        // A new name at the same position is a rename.
+        let before = total_changes(&store);
+        store.sync(&[view("Renamed", &settings, &second)]).unwrap();
+        assert_eq!(total_changes(&store) - before, 1);
+
+        let databases = store.load().unwrap();
+        assert_eq!(databases.len(), 1);
+        assert_eq!(databases[0].name, "Renamed");
+        assert_eq!(databases[0].cards, second);
+    }
+
+    #[test]
+    fn version_1_store_is_migrated() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE databases (
+                 position INTEGER PRIMARY KEY,
+                 name TEXT NOT NULL,
+                 settings TEXT NOT NULL
+             );
+             CREATE TABLE cards (
+                 database INTEGER NOT NULL,
+                 position INTEGER NOT NULL,
+                 category TEXT NOT NULL,
+                 text TEXT NOT NULL,
+                 weight REAL NOT NULL,
+                 enabled INTEGER NOT NULL,
+                 PRIMARY KEY (database, position)
+             );
+             CREATE INDEX cards_category ON cards (database, category);
+             CREATE INDEX cards_text ON cards (database, text);
+             INSERT INTO databases VALUES (0, 'Old', '{}');
+             INSERT INTO cards VALUES (0, 1, 'Combat', 'Goal 2', 1.0, 1);
+             INSERT INTO cards VALUES (0, 0, 'Combat', 'Goal 1', 1.0, 1);
+             PRAGMA user_version = 1;",
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+        let mut store = CardStore {
+            conn,
+            written: vec![],
+        };
+
+        let databases = store.load().unwrap();
+        assert_eq!(databases[0].name, "Old");
+        assert_eq!(databases[0].cards, cards(2));
+    }
+}
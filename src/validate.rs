@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{
+    board::{FieldSize, BOARD_CELLS},
+    card::CardRow,
+};
+
+pub(crate) struct LineStats {
+    pub(crate) name: String,
+    pub(crate) weight: f64,
+}
+
+/// Summary of what the current board is made of.
+#[derive(Default)]
+pub(crate) struct BoardReport {
+    pub(crate) lines: Vec<LineStats>,
+    pub(crate) categories: BTreeMap<String, usize>,
+    pub(crate) duplicates: Vec<String>,
+    pub(crate) missing: Vec<String>,
+    pub(crate) empty: usize,
+    /// Board indices of cells with any of the problems above.
+    pub(crate) warnings: HashSet<usize>,
+}
+
+impl BoardReport {
+    pub(crate) fn new<'a>(
+        board: &[String; BOARD_CELLS],
+        size: FieldSize,
+        free_cell: Option<usize>,
+        cards: impl Iterator<Item = &'a CardRow>,
+    ) -> Self {
+        let mut lookup: HashMap<&str, &CardRow> = HashMap::new();
+        for card in cards {
+            lookup.entry(card.1.as_str()).or_insert(card);
+        }
+
+        let mut report = Self::default();
+        let cells: Vec<usize> = size
+            .cells()
+            .into_iter()
+            .filter(|idx| Some(*idx) != free_cell)
+            .collect();
+
+        for idx in cells.iter().copied() {
+            let text = &board[idx];
+
+            if text.trim().is_empty() {
+                report.empty += 1;
+                report.warnings.insert(idx);
+                continue;
+            }
+
+            match lookup.get(text.as_str()) {
+                Some(card) => {
+                    *report.categories.entry(card.0.to_owned()).or_default() += 1;
+                }
+                None => {
+                    report.missing.push(text.to_owned());
+                    report.warnings.insert(idx);
+                }
+            }
+        }
+
+        let counts = cells
+            .iter()
+            .map(|idx| board[*idx].as_str())
+            .filter(|text| !text.trim().is_empty())
+            .counts();
+        for idx in cells.iter().copied() {
+            if counts
+                .get(board[idx].as_str())
+                .is_some_and(|count| *count > 1)
+            {
+                report.warnings.insert(idx);
+            }
+        }
+        report.duplicates = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(text, _)| text.to_owned())
+            .sorted()
+            .collect();
+
+        report.lines = size
+            .lines()
+            .into_iter()
+            .map(|(name, line)| LineStats {
+                name,
+                weight: line
+                    .iter()
+                    .filter(|idx| Some(**idx) != free_cell)
+                    .filter_map(|idx| lookup.get(board[*idx].as_str()))
+                    .map(|card| card.2)
+                    .sum(),
+            })
+            .collect();
+
+        report
+    }
+}